@@ -11,12 +11,25 @@ impl FromStr for Problem {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (first_list, second_list) = s
-            .lines()
+        Problem::from_lines(s.lines().map(String::from))
+    }
+}
+
+impl Problem {
+    /// Parses the lists from lines fed one at a time, e.g. by
+    /// `BufRead::lines`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a line doesn't hold a pair of numbers.
+    pub fn from_lines<I: Iterator<Item = String>>(lines: I) -> Result<Problem, anyhow::Error> {
+        let (first_list, second_list) = lines
             .map(|l| -> Result<(i32, i32), anyhow::Error> {
-                let (a, b) = l
-                    .split_once("   ")
-                    .ok_or_else(|| anyhow!("Couldn't split pair by delimiter"))?;
+                let mut numbers = l.split_whitespace();
+
+                let (Some(a), Some(b)) = (numbers.next(), numbers.next()) else {
+                    return Err(anyhow!("Expected a pair of numbers"));
+                };
 
                 Ok((a.parse()?, b.parse()?))
             })
@@ -32,24 +45,44 @@ impl FromStr for Problem {
             second_list,
         })
     }
-}
 
-#[must_use]
-pub fn solve_part_1(p: &Problem) -> i32 {
-    let Problem {
-        first_list,
-        second_list,
-    } = p;
+    // smallest number of the first list paired with the smallest of the
+    // second one, then the second smallest ones, and so on
+    pub fn sorted_pairs(&self) -> impl Iterator<Item = (i32, i32)> {
+        let Problem {
+            first_list,
+            second_list,
+        } = self;
 
-    let (first_list, second_list) = {
         let mut first_list = first_list.clone();
         let mut second_list = second_list.clone();
 
         first_list.sort_unstable();
         second_list.sort_unstable();
 
-        (first_list, second_list)
-    };
+        first_list.into_iter().zip(second_list)
+    }
+}
+
+#[must_use]
+pub fn solve_part_1(p: &Problem) -> i32 {
+    solve_part_1_with(p, |a, b| (a - b).abs())
+}
+
+// sums `metric` over the pairs of numbers matched up after sorting both lists
+#[must_use]
+pub fn solve_part_1_with<F: Fn(i32, i32) -> i32>(p: &Problem, metric: F) -> i32 {
+    p.sorted_pairs().map(|(a, b)| metric(a, b)).sum()
+}
+
+// total distance between numbers paired by their original position in the
+// lists, without sorting
+#[must_use]
+pub fn solve_part_1_unsorted(p: &Problem) -> i32 {
+    let Problem {
+        first_list,
+        second_list,
+    } = p;
 
     first_list
         .iter()
@@ -81,6 +114,42 @@ pub fn solve_part_2(p: &Problem) -> i32 {
         .sum()
 }
 
+// computes both parts at once - the right list is copied (for sorting) and
+// counted (for similarity) in the same pass
+#[must_use]
+pub fn solve_both(p: &Problem) -> (i32, i64) {
+    let Problem {
+        first_list,
+        second_list,
+    } = p;
+
+    let mut first_list = first_list.clone();
+    first_list.sort_unstable();
+
+    let mut sorted_second_list = Vec::with_capacity(second_list.len());
+    let mut counts: HashMap<i32, i64> = HashMap::new();
+
+    for n in second_list {
+        sorted_second_list.push(*n);
+        *counts.entry(*n).or_default() += 1;
+    }
+
+    sorted_second_list.sort_unstable();
+
+    let distance = first_list
+        .iter()
+        .zip(sorted_second_list.iter())
+        .map(|(a, b)| (a - b).abs())
+        .sum();
+
+    let similarity = first_list
+        .iter()
+        .map(|a| i64::from(*a) * counts.get(a).unwrap_or(&0))
+        .sum();
+
+    (distance, similarity)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,7 +172,37 @@ mod tests {
                 first_list: vec![3, 4, 2, 1, 3, 3],
                 second_list: vec![4, 3, 5, 3, 9, 3],
             }
-        )
+        );
+    }
+
+    #[test]
+    fn test_problem_from_lines() {
+        let p = Problem::from_lines(TEST_INPUT.lines().map(String::from)).unwrap();
+
+        assert_eq!(p, TEST_INPUT.parse().unwrap());
+        assert!(Problem::from_lines(["1 2".to_string(), "3".to_string()].into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_problem_parsing_ragged_whitespace() {
+        let p: Problem = "3 4\n4     3\n2\t5\n  1   3  \n3   9\n3 \t 3"
+            .parse()
+            .unwrap();
+
+        assert_eq!(p, TEST_INPUT.parse().unwrap());
+        assert!("3 4\n4".parse::<Problem>().is_err());
+        assert!("3 4\n\n".parse::<Problem>().is_err());
+    }
+
+    #[test]
+    fn test_sorted_pairs() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(p.sorted_pairs().next(), Some((1, 3)));
+        assert_eq!(
+            p.sorted_pairs().collect::<Vec<_>>(),
+            vec![(1, 3), (2, 3), (3, 3), (3, 4), (3, 5), (4, 9)]
+        );
     }
 
     #[test]
@@ -113,10 +212,37 @@ mod tests {
         assert_eq!(solve_part_1(&p), 11);
     }
 
+    #[test]
+    fn test_solve_part_1_with() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(solve_part_1_with(&p, |a, b| (a - b).abs()), 11);
+        // sorted pairs: (1, 3), (2, 3), (3, 3), (3, 4), (3, 5), (4, 9)
+        assert_eq!(
+            solve_part_1_with(&p, |a, b| (a - b).pow(2)),
+            4 + 1 + 1 + 4 + 25
+        );
+    }
+
+    #[test]
+    fn test_solve_part_1_unsorted() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(solve_part_1_unsorted(&p), 1 + 1 + 3 + 2 + 6);
+        assert_ne!(solve_part_1_unsorted(&p), solve_part_1(&p));
+    }
+
     #[test]
     fn test_solve_part_2() {
         let p: Problem = TEST_INPUT.parse().unwrap();
 
         assert_eq!(solve_part_2(&p), 31);
     }
+
+    #[test]
+    fn test_solve_both() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(solve_both(&p), (11, 31));
+    }
 }
@@ -11,42 +11,107 @@ impl FromStr for Problem {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let reports = s
-            .lines()
-            .map(|l| {
-                let record = l
-                    .split_ascii_whitespace()
-                    .map(str::parse)
-                    .collect::<Result<Vec<_>, _>>()?;
+        let reports = s.lines().map(parse_record).collect::<Result<_, _>>()?;
 
-                ensure!(record.len() > 1, "Record has at least two entries");
+        Ok(Problem { reports })
+    }
+}
 
-                Ok(record)
-            })
-            .collect::<Result<_, _>>()?;
+fn parse_record(s: &str) -> Result<Vec<i32>, anyhow::Error> {
+    let record = s
+        .split_ascii_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Problem { reports })
+    ensure!(record.len() > 1, "Record has at least two entries");
+
+    Ok(record)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Trend {
+    Increasing,
+    Decreasing,
+    Neither,
+}
+
+// whether levels are strictly increasing or strictly decreasing, regardless
+// of the step sizes
+#[must_use]
+pub fn report_trend(record: &[i32]) -> Trend {
+    if record.windows(2).all(|levels| levels[0] < levels[1]) {
+        Trend::Increasing
+    } else if record.windows(2).all(|levels| levels[0] > levels[1]) {
+        Trend::Decreasing
+    } else {
+        Trend::Neither
     }
 }
 
-fn is_safe(record: &[i32]) -> bool {
-    let increasing = record[0] < record[1];
+// index of the first level that either changes by other than 1 to 3 from
+// the previous one, or goes against the direction set by the first two levels
+#[must_use]
+pub fn first_unsafe_index(record: &[i32]) -> Option<usize> {
+    first_unsafe_index_with_bounds(record, 1, 3)
+}
+
+// same as `first_unsafe_index`, but levels may change by `min_step` to
+// `max_step` instead
+fn first_unsafe_index_with_bounds(record: &[i32], min_step: i32, max_step: i32) -> Option<usize> {
+    let direction = record
+        .windows(2)
+        .next()
+        .map_or(0, |levels| (levels[1] - levels[0]).signum());
 
     record
         .windows(2)
-        .map(|levels| levels[0] - levels[1])
-        .all(|diff| {
-            (increasing && (-3..=-1).contains(&diff)) || (!increasing && (1..=3).contains(&diff))
-        })
+        .map(|levels| levels[1] - levels[0])
+        .position(|diff| diff.signum() != direction || !(min_step..=max_step).contains(&diff.abs()))
+        .map(|idx| idx + 1)
+}
+
+fn is_safe(record: &[i32]) -> bool {
+    is_safe_with_bounds(record, 1, 3)
+}
+
+// whether levels consistently go in one direction, changing by `min_step` to
+// `max_step` at a time; reports shorter than two levels are trivially safe
+#[must_use]
+pub fn is_safe_with_bounds(record: &[i32], min_step: i32, max_step: i32) -> bool {
+    first_unsafe_index_with_bounds(record, min_step, max_step).is_none()
+}
+
+// like `is_safe`, but levels may also stay the same; the direction is set by
+// the first actual change
+#[must_use]
+pub fn is_safe_allow_equal(record: &[i32]) -> bool {
+    let mut diffs = record.windows(2).map(|levels| levels[1] - levels[0]);
+
+    let direction = diffs.clone().find(|diff| *diff != 0).map_or(0, i32::signum);
+
+    diffs.all(|diff| diff == 0 || (diff.signum() == direction && diff.abs() <= 3))
 }
 
 fn is_safe_with_dampener(record: &[i32]) -> bool {
+    is_safe_with_dampener_k(record, 1)
+}
+
+// whether the report is safe after removing at most `k` levels
+#[must_use]
+pub fn is_safe_with_dampener_k(record: &[i32], k: usize) -> bool {
     if is_safe(record) {
         return true;
     }
 
+    if k == 0 {
+        return false;
+    }
+
     for skip_idx in 0..record.len() {
-        if is_safe(&[&record[0..skip_idx], &record[(skip_idx + 1)..]].concat()) {
+        if is_safe_with_dampener_k(
+            &[&record[0..skip_idx], &record[(skip_idx + 1)..]].concat(),
+            k - 1,
+        ) {
             return true;
         }
     }
@@ -54,6 +119,81 @@ fn is_safe_with_dampener(record: &[i32]) -> bool {
     false
 }
 
+// `increasing` and `decreasing` count reports by `report_trend`, whether safe
+// or not
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    pub total: usize,
+    pub safe: usize,
+    pub safe_with_dampener: usize,
+    pub increasing: usize,
+    pub decreasing: usize,
+}
+
+impl Problem {
+    /// Parses reports given on a single line, separated by `;`, e.g.
+    /// `"7 6 4 2 1; 1 2 7 8 9"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a level isn't a number or a record has fewer than
+    /// two levels.
+    pub fn from_inline(s: &str) -> Result<Problem, anyhow::Error> {
+        let reports = s
+            .trim()
+            .split(';')
+            .map(parse_record)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Problem { reports })
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        let Problem { reports } = self;
+
+        let mut stats = Stats::default();
+
+        for report in reports {
+            stats.total += 1;
+
+            let safe = is_safe(report);
+
+            if safe {
+                stats.safe += 1;
+            }
+
+            if safe || is_safe_with_dampener(report) {
+                stats.safe_with_dampener += 1;
+            }
+
+            match report_trend(report) {
+                Trend::Increasing => stats.increasing += 1,
+                Trend::Decreasing => stats.decreasing += 1,
+                Trend::Neither => {}
+            }
+        }
+
+        stats
+    }
+
+    #[must_use]
+    pub fn safe_reports(&self, with_dampener: bool) -> Vec<&Vec<i32>> {
+        let Problem { reports } = self;
+
+        reports
+            .iter()
+            .filter(|report| {
+                if with_dampener {
+                    is_safe_with_dampener(report)
+                } else {
+                    is_safe(report)
+                }
+            })
+            .collect()
+    }
+}
+
 #[must_use]
 pub fn solve_part_1(p: &Problem) -> usize {
     let Problem { reports } = p;
@@ -91,6 +231,30 @@ mod tests {
         assert_eq!(p.reports[0].len(), 5);
     }
 
+    #[test]
+    fn test_problem_from_inline() {
+        let p = Problem::from_inline(&TEST_INPUT.replace('\n', "; ")).unwrap();
+
+        assert_eq!(p, TEST_INPUT.parse().unwrap());
+        assert_eq!(solve_part_1(&p), 2);
+
+        let p = Problem::from_inline("7 6 4 2 1;1 2 7 8 9\n").unwrap();
+
+        assert_eq!(p.reports, vec![vec![7, 6, 4, 2, 1], vec![1, 2, 7, 8, 9]]);
+
+        assert!(Problem::from_inline("7 6 4 2 1; 1").is_err());
+        assert!(Problem::from_inline("7 6 4 2 1;").is_err());
+    }
+
+    #[test]
+    fn test_report_trend() {
+        assert_eq!(report_trend(&[1, 3, 6, 7, 9]), Trend::Increasing);
+        assert_eq!(report_trend(&[9, 7, 6, 2, 1]), Trend::Decreasing);
+        assert_eq!(report_trend(&[1, 2, 7, 8, 9]), Trend::Increasing);
+        assert_eq!(report_trend(&[1, 3, 2, 4, 5]), Trend::Neither);
+        assert_eq!(report_trend(&[8, 6, 4, 4, 1]), Trend::Neither);
+    }
+
     #[test]
     fn test_is_safe_1() {
         assert!(is_safe(&[7, 6, 4, 2, 1]));
@@ -101,6 +265,40 @@ mod tests {
         assert!(!is_safe(&[1, 2, 7, 8, 9]));
     }
 
+    #[test]
+    fn test_is_safe_with_bounds() {
+        assert!(is_safe_with_bounds(&[1, 3, 6, 7, 9], 1, 3));
+        assert!(!is_safe_with_bounds(&[1, 3, 6, 7, 9], 1, 2));
+        assert!(is_safe_with_bounds(&[1, 3, 5, 6, 8], 1, 2));
+        assert!(is_safe_with_bounds(&[1, 2, 7, 8, 9], 1, 5));
+        assert!(!is_safe_with_bounds(&[1, 3, 5], 3, 5));
+        assert!(is_safe_with_bounds(&[5], 1, 3));
+        assert!(is_safe_with_bounds(&[], 1, 3));
+    }
+
+    #[test]
+    fn test_is_safe_allow_equal() {
+        assert!(!is_safe(&[1, 1, 2, 3]));
+        assert!(is_safe_allow_equal(&[1, 1, 2, 3]));
+        assert!(is_safe_allow_equal(&[5, 4, 4, 1]));
+        assert!(is_safe_allow_equal(&[2, 2, 2]));
+        assert!(!is_safe_allow_equal(&[1, 1, 2, 1]));
+        assert!(!is_safe_allow_equal(&[1, 1, 5]));
+        assert!(is_safe_allow_equal(&[7, 6, 4, 2, 1]));
+        assert!(is_safe_allow_equal(&[8, 6, 4, 4, 1]));
+    }
+
+    #[test]
+    fn test_first_unsafe_index() {
+        assert_eq!(first_unsafe_index(&[7, 6, 4, 2, 1]), None);
+        assert_eq!(first_unsafe_index(&[1, 2, 7, 8, 9]), Some(2));
+        assert_eq!(first_unsafe_index(&[9, 7, 6, 2, 1]), Some(3));
+        assert_eq!(first_unsafe_index(&[1, 3, 2, 4, 5]), Some(2));
+        assert_eq!(first_unsafe_index(&[8, 6, 4, 4, 1]), Some(3));
+        assert_eq!(first_unsafe_index(&[1, 1, 2]), Some(1));
+        assert_eq!(first_unsafe_index(&[1]), None);
+    }
+
     #[test]
     fn test_solve_part_1() {
         let p: Problem = TEST_INPUT.parse().unwrap();
@@ -108,6 +306,33 @@ mod tests {
         assert_eq!(solve_part_1(&p), 2);
     }
 
+    #[test]
+    fn test_safe_reports() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(
+            p.safe_reports(false),
+            vec![&vec![7, 6, 4, 2, 1], &vec![1, 3, 6, 7, 9]]
+        );
+        assert_eq!(p.safe_reports(true).len(), 4);
+    }
+
+    #[test]
+    fn test_stats() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(
+            p.stats(),
+            Stats {
+                total: 6,
+                safe: 2,
+                safe_with_dampener: 4,
+                increasing: 2,
+                decreasing: 2,
+            }
+        );
+    }
+
     #[test]
     fn test_is_safe_with_dampener_1() {
         assert!(is_safe_with_dampener(&[3, 2, 3, 4, 5, 6]));
@@ -118,6 +343,35 @@ mod tests {
         assert!(is_safe_with_dampener(&[1, 2, 3, 4, 5, 4]));
     }
 
+    #[test]
+    fn test_is_safe_with_dampener_k() {
+        // both 9 and 0 have to go
+        let record = [1, 2, 9, 3, 0, 4];
+
+        assert!(!is_safe_with_dampener_k(&record, 0));
+        assert!(!is_safe_with_dampener_k(&record, 1));
+        assert!(is_safe_with_dampener_k(&record, 2));
+        assert!(is_safe_with_dampener_k(&record, 3));
+
+        assert!(is_safe_with_dampener_k(&[7, 6, 4, 2, 1], 0));
+        assert_eq!(
+            is_safe_with_dampener_k(&[1, 3, 2, 4, 5], 1),
+            is_safe_with_dampener(&[1, 3, 2, 4, 5])
+        );
+    }
+
+    #[test]
+    fn test_is_safe_with_dampener_reinfers_direction() {
+        // rises then falls twice, no single removal makes it monotonic
+        assert!(!is_safe_with_dampener(&[1, 2, 3, 4, 3, 2]));
+        // first pair is falling, but dropping the first level leaves a
+        // rising report
+        assert!(is_safe_with_dampener(&[5, 1, 2, 3]));
+        // first pair is rising, but dropping the second level leaves a
+        // falling report
+        assert!(is_safe_with_dampener(&[4, 5, 3, 2, 1]));
+    }
+
     #[test]
     fn test_solve_part_2() {
         let p: Problem = TEST_INPUT.parse().unwrap();
@@ -1,3 +1,4 @@
+use anyhow::ensure;
 use std::{
     iter::Peekable,
     str::{Chars, FromStr},
@@ -35,31 +36,26 @@ impl<'a> ProgramParser<'a> {
     }
 
     fn parse(&mut self) -> Vec<Instruction> {
-        let mut result = vec![];
-
-        while self.iterator.peek().is_some() {
-            if self.parse_literal("do()") {
-                result.push(Instruction::Do);
-            } else if self.parse_literal("don't()") {
-                result.push(Instruction::Dont);
-            } else if self.parse_literal("mul") {
-                if self.parse_literal("(") {
-                    if let Some(a) = self.parse_number() {
-                        if self.parse_literal(",") {
-                            if let Some(b) = self.parse_number() {
-                                if self.parse_literal(")") {
-                                    result.push(Instruction::Mul(a, b));
-                                }
-                            }
-                        }
-                    }
-                }
-            } else {
-                let _ = self.iterator.next();
-            }
-        }
+        self.collect()
+    }
 
-        result
+    // parses no further than one instruction past `max_instructions`, so an
+    // oversized program is rejected without parsing the rest of it
+    fn parse_at_most(
+        &mut self,
+        max_instructions: usize,
+    ) -> Result<Vec<Instruction>, anyhow::Error> {
+        let program: Vec<_> = self
+            .by_ref()
+            .take(max_instructions.saturating_add(1))
+            .collect();
+
+        ensure!(
+            program.len() <= max_instructions,
+            "Program has more than {max_instructions} instructions"
+        );
+
+        Ok(program)
     }
 
     fn parse_literal(&mut self, literal: &str) -> bool {
@@ -107,12 +103,37 @@ impl<'a> ProgramParser<'a> {
     }
 }
 
-#[must_use]
-pub fn solve_part_1(p: &Problem) -> u64 {
-    let Problem { program } = p;
-    let mut parser = ProgramParser::new(program);
-    let program = parser.parse();
+impl Iterator for ProgramParser<'_> {
+    type Item = Instruction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.iterator.peek().is_some() {
+            if self.parse_literal("do()") {
+                return Some(Instruction::Do);
+            } else if self.parse_literal("don't()") {
+                return Some(Instruction::Dont);
+            } else if self.parse_literal("mul") {
+                if self.parse_literal("(") {
+                    if let Some(a) = self.parse_number() {
+                        if self.parse_literal(",") {
+                            if let Some(b) = self.parse_number() {
+                                if self.parse_literal(")") {
+                                    return Some(Instruction::Mul(a, b));
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                let _ = self.iterator.next();
+            }
+        }
 
+        None
+    }
+}
+
+fn sum_muls(program: &[Instruction]) -> u64 {
     let mut result = 0;
 
     for instr in program {
@@ -124,6 +145,29 @@ pub fn solve_part_1(p: &Problem) -> u64 {
     result
 }
 
+#[must_use]
+pub fn solve_part_1(p: &Problem) -> u64 {
+    let Problem { program } = p;
+    let mut parser = ProgramParser::new(program);
+    let program = parser.parse();
+
+    sum_muls(&program)
+}
+
+/// Same as [`solve_part_1`], but refuses to evaluate programs made of more
+/// than `max_instructions` instructions.
+///
+/// # Errors
+///
+/// Returns an error if the parsed program exceeds `max_instructions`.
+pub fn solve_part_1_limited(p: &Problem, max_instructions: usize) -> Result<u64, anyhow::Error> {
+    let Problem { program } = p;
+    let mut parser = ProgramParser::new(program);
+    let program = parser.parse_at_most(max_instructions)?;
+
+    Ok(sum_muls(&program))
+}
+
 #[must_use]
 pub fn solve_part_2(p: &Problem) -> u64 {
     let Problem { program } = p;
@@ -165,6 +209,34 @@ mod tests {
         assert_eq!(solve_part_1(&p), 161);
     }
 
+    #[test]
+    fn test_solve_part_1_limited() {
+        let p: Problem = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))"
+            .parse()
+            .unwrap();
+
+        assert!(solve_part_1_limited(&p, 3).is_err());
+        assert_eq!(solve_part_1_limited(&p, 4).unwrap(), 161);
+        assert_eq!(solve_part_1_limited(&p, 100).unwrap(), 161);
+    }
+
+    #[test]
+    fn test_parse_at_most_stops_early() {
+        let program = "mul(1,1)".repeat(1000);
+        let mut parser = ProgramParser::new(&program);
+
+        assert!(parser.parse_at_most(3).is_err());
+        // stopped right after the fourth instruction
+        assert_eq!(
+            parser.iterator.count(),
+            program.len() - 4 * "mul(1,1)".len()
+        );
+
+        let mut parser = ProgramParser::new(&program);
+
+        assert_eq!(parser.parse_at_most(1000).unwrap().len(), 1000);
+    }
+
     #[test]
     fn test_solve_part_2() {
         let p: Problem =
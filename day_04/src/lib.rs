@@ -15,121 +15,214 @@ impl FromStr for Problem {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let chars = s.lines().map(|line| line.chars().collect()).collect();
+        // `lines` strips only a single `\r` in front of each `\n`, drop any
+        // stray ones left at the line ends
+        let chars = s
+            .lines()
+            .map(|line| line.trim_end_matches('\r').chars().collect())
+            .collect();
 
         Ok(Problem { chars })
     }
 }
 
-fn count_xmas(chars: &[Vec<char>]) -> usize {
-    let mut count = 0;
+// (dx, dy) steps of all eight directions a word can be read in
+pub const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
 
-    let term: Vec<char> = "XMAS".chars().collect();
-    let term_rev: Vec<char> = "SAMX".chars().collect();
-    let term_len = term.len();
+fn matches_in_direction(
+    chars: &[Vec<char>],
+    term: &[char],
+    start: (usize, usize),
+    delta: (isize, isize),
+) -> bool {
+    matches_in_direction_by(chars, term, start, delta, |cell, c| cell == c)
+}
 
-    let chars_height = chars.len();
-    let chars_width = chars[0].len();
-
-    for x in 0..chars_height {
-        for y in 0..chars_width {
-            if (0..=(chars_height - term_len)).contains(&x) {
-                // top-down
-                if (0..term_len).all(|d| chars[x + d][y] == term[d]) {
-                    count += 1;
-                }
-                // bottom-top
-                if (0..term_len).all(|d| chars[x + d][y] == term_rev[d]) {
-                    count += 1;
-                }
-            }
+// same as `matches_in_direction`, but grid cells are compared with the term's
+// letters using `eq`
+fn matches_in_direction_by(
+    chars: &[Vec<char>],
+    term: &[char],
+    start: (usize, usize),
+    delta: (isize, isize),
+    eq: impl Fn(char, char) -> bool,
+) -> bool {
+    let mut position = Some(start);
 
-            if (0..=(chars_width - term_len)).contains(&y) {
-                // left-right
-                if (0..term_len).all(|d| chars[x][y + d] == term[d]) {
-                    count += 1;
-                }
-                // right-left
-                if (0..term_len).all(|d| chars[x][y + d] == term_rev[d]) {
-                    count += 1;
-                }
-            }
+    for c in term {
+        let Some((x, y)) = position else {
+            // stepped off the top or the left edge
+            return false;
+        };
 
-            if (0..=(chars_height - term_len)).contains(&x)
-                && (0..=(chars_width - term_len)).contains(&y)
-            {
-                // diagonal, top-left to bottom-right
-                if (0..term_len).all(|d| chars[x + d][y + d] == term[d]) {
-                    count += 1;
-                }
-                // diagonal, bottom-right to top-left
-                if (0..term_len).all(|d| chars[x + d][y + d] == term_rev[d]) {
-                    count += 1;
-                }
+        if !chars
+            .get(x)
+            .and_then(|row| row.get(y))
+            .is_some_and(|cell| eq(*cell, *c))
+        {
+            return false;
+        }
+
+        position = x
+            .checked_add_signed(delta.0)
+            .zip(y.checked_add_signed(delta.1));
+    }
+
+    true
+}
+
+// occurrences of `word` read in any of the eight `DIRECTIONS`
+#[must_use]
+pub fn count_word(chars: &[Vec<char>], word: &str) -> usize {
+    DIRECTIONS
+        .iter()
+        .map(|delta| count_word_direction(chars, word, *delta))
+        .sum()
+}
+
+// occurrences of `word` read by stepping `delta` from one letter to the next
+#[must_use]
+pub fn count_word_direction(chars: &[Vec<char>], word: &str, delta: (isize, isize)) -> usize {
+    count_word_direction_by(chars, word, delta, |cell, c| cell == c)
+}
+
+// same as `count_word_direction`, but grid cells are compared with the word's
+// letters using `eq`
+fn count_word_direction_by(
+    chars: &[Vec<char>],
+    word: &str,
+    delta: (isize, isize),
+    eq: impl Fn(char, char) -> bool,
+) -> usize {
+    let term: Vec<char> = word.chars().collect();
+
+    let mut count = 0;
+
+    for x in 0..chars.len() {
+        for y in 0..chars[x].len() {
+            if matches_in_direction_by(chars, &term, (x, y), delta, &eq) {
+                count += 1;
             }
+        }
+    }
 
-            if (0..=(chars_height - term_len)).contains(&x)
-                && ((term_len - 1)..chars_width).contains(&y)
-            {
-                // diagonal, top-right to bottom-left
-                if (0..term_len).all(|d| chars[x + d][y - d] == term[d]) {
-                    count += 1;
+    count
+}
+
+// like `count_word`, but ignoring the case of both the grid and `word`
+#[must_use]
+pub fn count_word_ci(chars: &[Vec<char>], word: &str) -> usize {
+    let eq_ignoring_case = |cell: char, c: char| cell.to_lowercase().eq(c.to_lowercase());
+
+    DIRECTIONS
+        .iter()
+        .map(|delta| count_word_direction_by(chars, word, *delta, eq_ignoring_case))
+        .sum()
+}
+
+// like `count_word`, but only matches lying entirely within the rectangle
+// spanned by the (inclusive) `top_left` and `bottom_right` corners
+#[must_use]
+pub fn count_word_in_region(
+    chars: &[Vec<char>],
+    word: &str,
+    top_left: (usize, usize),
+    bottom_right: (usize, usize),
+) -> usize {
+    let region: Vec<Vec<char>> = chars
+        .iter()
+        .take(bottom_right.0 + 1)
+        .skip(top_left.0)
+        .map(|row| {
+            row.iter()
+                .take(bottom_right.1 + 1)
+                .skip(top_left.1)
+                .copied()
+                .collect()
+        })
+        .collect();
+
+    count_word(&region, word)
+}
+
+// copy of the grid with every cell not part of some occurrence of `word`
+// (read in any of the eight `DIRECTIONS`) replaced by `.`
+#[must_use]
+pub fn highlight(chars: &[Vec<char>], word: &str) -> Vec<Vec<char>> {
+    let term: Vec<char> = word.chars().collect();
+
+    let mut highlighted: Vec<Vec<char>> = chars.iter().map(|row| vec!['.'; row.len()]).collect();
+
+    for x in 0..chars.len() {
+        for y in 0..chars[x].len() {
+            for delta in DIRECTIONS {
+                if !matches_in_direction(chars, &term, (x, y), delta) {
+                    continue;
                 }
-                // diagonal, bottom-left to top-right
-                if (0..term_len).all(|d| chars[x + d][y - d] == term_rev[d]) {
-                    count += 1;
+
+                let mut position = (x, y);
+
+                for _ in 0..term.len() {
+                    highlighted[position.0][position.1] = chars[position.0][position.1];
+
+                    // may step off the grid only after the last letter
+                    position = (
+                        position.0.wrapping_add_signed(delta.0),
+                        position.1.wrapping_add_signed(delta.1),
+                    );
                 }
             }
         }
     }
 
-    count
+    highlighted
+}
+
+fn count_xmas(chars: &[Vec<char>]) -> usize {
+    count_word(chars, "XMAS")
 }
 
 fn count_x_mas_duh(chars: &[Vec<char>]) -> usize {
+    count_cross(chars, 'A', &[('M', 'S')])
+}
+
+// counts `center` letters whose both diagonals are capped with one of the
+// `arm_pairs`, read in either direction, e.g. for `'A'` and `('M', 'S')`:
+//
+// M S
+//  A
+// M S
+#[must_use]
+pub fn count_cross(chars: &[Vec<char>], center: char, arm_pairs: &[(char, char)]) -> usize {
+    let is_arm = |a: char, b: char| {
+        arm_pairs
+            .iter()
+            .any(|&(first, second)| (a, b) == (first, second) || (a, b) == (second, first))
+    };
+
     let mut count = 0;
 
     let chars_height = chars.len();
-    let chars_width = chars[0].len();
-
-    for x in 1..(chars_height - 1) {
-        for y in 1..(chars_width - 1) {
-            if chars[x][y] == 'A' {
-                // M M
-                //  A
-                // S S
-                if (chars[x - 1][y - 1] == 'M' && chars[x + 1][y + 1] == 'S')
-                    && (chars[x - 1][y + 1] == 'M' && chars[x + 1][y - 1] == 'S')
-                {
-                    count += 1;
-                }
-                // first flipped
-                // S M
-                //  A
-                // S M
-                if (chars[x - 1][y - 1] == 'S' && chars[x + 1][y + 1] == 'M')
-                    && (chars[x - 1][y + 1] == 'M' && chars[x + 1][y - 1] == 'S')
-                {
-                    count += 1;
-                }
-                // second flipped
-                // M S
-                //  A
-                // M S
-                if (chars[x - 1][y - 1] == 'M' && chars[x + 1][y + 1] == 'S')
-                    && (chars[x - 1][y + 1] == 'S' && chars[x + 1][y - 1] == 'M')
-                {
-                    count += 1;
-                }
-                // both flipped
-                // S S
-                //  A
-                // M M
-                if (chars[x - 1][y - 1] == 'S' && chars[x + 1][y + 1] == 'M')
-                    && (chars[x - 1][y + 1] == 'S' && chars[x + 1][y - 1] == 'M')
-                {
-                    count += 1;
-                }
+    let chars_width = chars.first().map_or(0, Vec::len);
+
+    for x in 1..chars_height.saturating_sub(1) {
+        for y in 1..chars_width.saturating_sub(1) {
+            if chars[x][y] == center
+                // diagonal, top-left to bottom-right
+                && is_arm(chars[x - 1][y - 1], chars[x + 1][y + 1])
+                // diagonal, top-right to bottom-left
+                && is_arm(chars[x - 1][y + 1], chars[x + 1][y - 1])
+            {
+                count += 1;
             }
         }
     }
@@ -175,6 +268,136 @@ MXMXAXMASX";
         assert_eq!(count_xmas(&p.chars), 18);
     }
 
+    #[test]
+    fn test_count_xmas_all_directions() {
+        let p: Problem = "\
+S..S..S
+.A.A.A.
+..MMM..
+SAMXMAS
+..MMM..
+.A.A.A.
+S..S..S"
+            .parse()
+            .unwrap();
+
+        let term: Vec<char> = "XMAS".chars().collect();
+
+        for delta in DIRECTIONS {
+            assert!(matches_in_direction(&p.chars, &term, (3, 3), delta));
+        }
+
+        assert_eq!(count_xmas(&p.chars), 8);
+    }
+
+    #[test]
+    fn test_highlight() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let expected: Problem = "\
+....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX"
+            .parse()
+            .unwrap();
+
+        let highlighted = highlight(&p.chars, "XMAS");
+
+        assert_eq!(highlighted, expected.chars);
+        assert_eq!(count_xmas(&highlighted), 18);
+    }
+
+    #[test]
+    fn test_count_word_direction() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(count_word_direction(&p.chars, "XMAS", (0, 1)), 3);
+        assert_eq!(count_word_direction(&p.chars, "XMAS", (0, -1)), 2);
+        assert_eq!(
+            DIRECTIONS
+                .iter()
+                .map(|delta| count_word_direction(&p.chars, "XMAS", *delta))
+                .sum::<usize>(),
+            18
+        );
+    }
+
+    #[test]
+    fn test_count_word_ci() {
+        let p: Problem = "\
+XMAS.xmas
+.........
+XmAs.SAMX"
+            .parse()
+            .unwrap();
+
+        assert_eq!(count_word(&p.chars, "XMAS"), 2);
+        assert_eq!(count_word_ci(&p.chars, "XMAS"), 4);
+        assert_eq!(count_word_ci(&p.chars, "xmas"), 4);
+        assert!(count_word_ci(&p.chars, "XMAS") > count_word(&p.chars, "XMAS"));
+
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(count_word_ci(&p.chars, "xMaS"), 18);
+    }
+
+    #[test]
+    fn test_count_word_in_region() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        // MMMSX
+        // MSAMX
+        // AMXSX
+        // MSAMA
+        // XMASA
+        assert_eq!(count_word_in_region(&p.chars, "XMAS", (0, 0), (4, 4)), 2);
+        assert_eq!(count_word_in_region(&p.chars, "XMAS", (0, 0), (9, 9)), 18);
+        assert_eq!(count_word_in_region(&p.chars, "XMAS", (4, 0), (4, 3)), 1);
+        assert_eq!(count_word_in_region(&p.chars, "XMAS", (4, 0), (4, 2)), 0);
+    }
+
+    #[test]
+    fn test_problem_parsing_crlf() {
+        let p: Problem = TEST_INPUT.replace('\n', "\r\n").parse().unwrap();
+
+        assert!(p.chars.iter().all(|line| line.len() == 10));
+        assert_eq!(count_xmas(&p.chars), 18);
+
+        let p: Problem = TEST_INPUT.replace('\n', "\r\r\n").parse().unwrap();
+
+        assert!(p.chars.iter().all(|line| line.len() == 10));
+        assert_eq!(count_xmas(&p.chars), 18);
+    }
+
+    #[test]
+    fn test_count_cross() {
+        let p: Problem = "\
+X.X.Y
+.O.O.
+Y.Y.X"
+            .parse()
+            .unwrap();
+
+        assert_eq!(count_cross(&p.chars, 'O', &[('X', 'Y')]), 1);
+        assert_eq!(count_cross(&p.chars, 'O', &[('Y', 'X')]), 1);
+        assert_eq!(count_cross(&p.chars, 'O', &[('X', 'X')]), 0);
+        assert_eq!(count_cross(&p.chars, 'O', &[('X', 'X'), ('Y', 'Y')]), 1);
+        assert_eq!(
+            count_cross(&p.chars, 'O', &[('X', 'X'), ('Y', 'Y'), ('X', 'Y')]),
+            2
+        );
+        assert_eq!(count_cross(&p.chars, 'A', &[('M', 'S')]), 0);
+        assert_eq!(count_cross(&[], 'O', &[('X', 'Y')]), 0);
+        assert_eq!(count_cross(&[vec![]], 'O', &[('X', 'Y')]), 0);
+    }
+
     #[test]
     fn test_count_x_mas_duh() {
         let p: Problem = TEST_INPUT.parse().unwrap();
@@ -1,5 +1,6 @@
 use anyhow::anyhow;
 use std::{
+    cmp::{Ordering, Reverse},
     collections::{HashMap, HashSet},
     str::FromStr,
 };
@@ -53,8 +54,79 @@ impl FromStr for Problem {
     }
 }
 
+impl Problem {
+    // sum of the first and the last page of every correctly-ordered update
+    #[must_use]
+    pub fn sum_edge_pages(&self) -> usize {
+        let Problem { rules, updates } = self;
+
+        let mut pages_buffer = HashSet::new();
+
+        updates
+            .iter()
+            .filter(|update| is_valid_ordering_with_buffer(update, rules, &mut pages_buffer))
+            .map(|update| update[0] + update[update.len() - 1])
+            .sum()
+    }
+
+    // every page mentioned by any rule or update
+    #[must_use]
+    pub fn all_pages(&self) -> HashSet<usize> {
+        let Problem { rules, updates } = self;
+
+        rules
+            .iter()
+            .flat_map(|(page, pages_after)| pages_after.iter().chain([page]))
+            .chain(updates.iter().flatten())
+            .copied()
+            .collect()
+    }
+
+    // page that must be printed before the most other pages, along with the
+    // number of such pages (ties go to the lower page number)
+    #[must_use]
+    pub fn most_constrained_page(&self) -> Option<(usize, usize)> {
+        let Problem { rules, .. } = self;
+
+        rules
+            .iter()
+            .map(|(page, pages_after)| (*page, pages_after.len()))
+            .max_by_key(|&(page, count)| (count, Reverse(page)))
+    }
+
+    // same rules, with every incorrectly-ordered update replaced by its fixed
+    // ordering
+    #[must_use]
+    pub fn into_all_valid(self) -> Problem {
+        let Problem { rules, updates } = self;
+
+        let updates = updates
+            .into_iter()
+            .map(|update| {
+                if is_valid_ordering(&update, &rules) {
+                    update
+                } else {
+                    fix_ordering(&update, &rules)
+                }
+            })
+            .collect();
+
+        Problem { rules, updates }
+    }
+}
+
 fn is_valid_ordering(update: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> bool {
-    let mut pages_before_current_page: HashSet<usize> = HashSet::new();
+    is_valid_ordering_with_buffer(update, rules, &mut HashSet::new())
+}
+
+// same as `is_valid_ordering`, but reuses caller-provided `pages_before_current_page`
+// set (cleared on entry) instead of allocating a fresh one for every update
+fn is_valid_ordering_with_buffer(
+    update: &[usize],
+    rules: &HashMap<usize, HashSet<usize>>,
+    pages_before_current_page: &mut HashSet<usize>,
+) -> bool {
+    pages_before_current_page.clear();
 
     for current_page in update {
         if let Some(pages_that_must_be_after_current_page) = rules.get(current_page) {
@@ -74,8 +146,70 @@ fn is_valid_ordering(update: &[usize], rules: &HashMap<usize, HashSet<usize>>) -
     true
 }
 
+// stronger than `is_valid_ordering` - every page must be directly required
+// by a rule to come before the next one, not merely not violate any rule
+#[allow(clippy::implicit_hasher)]
+#[must_use]
+pub fn is_fully_ordered(update: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> bool {
+    update
+        .windows(2)
+        .all(|pages| page_cmp(pages[0], pages[1], rules) == Ordering::Less)
+}
+
+// which of the two middle pages to pick for an even-length update
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MiddleBias {
+    Lower,
+    Upper,
+}
+
+// puzzle guarantees updates of odd length, for which both biases pick the
+// very same page
 fn get_middle_page(update: &[usize]) -> usize {
-    update[update.len().div_ceil(2) - 1]
+    get_middle_page_with(update, MiddleBias::Lower)
+}
+
+#[must_use]
+pub fn get_middle_page_with(update: &[usize], bias: MiddleBias) -> usize {
+    match bias {
+        MiddleBias::Lower => update[update.len().div_ceil(2) - 1],
+        MiddleBias::Upper => update[update.len() / 2],
+    }
+}
+
+// orders `a` before `b` when a rule says so (and vice versa), pages not
+// related by any rule compare as equal
+fn page_cmp(a: usize, b: usize, rules: &HashMap<usize, HashSet<usize>>) -> Ordering {
+    if rules
+        .get(&a)
+        .is_some_and(|pages_after| pages_after.contains(&b))
+    {
+        Ordering::Less
+    } else if rules
+        .get(&b)
+        .is_some_and(|pages_after| pages_after.contains(&a))
+    {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+// number of adjacent swaps needed to put the update in rule order, i.e. the
+// number of page pairs printed in the wrong order
+#[allow(clippy::implicit_hasher)]
+#[must_use]
+pub fn fix_distance(update: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> usize {
+    update
+        .iter()
+        .enumerate()
+        .map(|(i, &a)| {
+            update[(i + 1)..]
+                .iter()
+                .filter(|&&b| page_cmp(a, b, rules) == Ordering::Greater)
+                .count()
+        })
+        .sum()
 }
 
 fn fix_ordering(update: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> Vec<usize> {
@@ -139,15 +273,33 @@ fn fix_ordering(update: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> Vec
 pub fn solve_part_1(p: &Problem) -> usize {
     let Problem { rules, updates } = p;
 
+    let mut pages_buffer = HashSet::new();
+
     let valid_updates = updates
         .iter()
-        .filter(|update| is_valid_ordering(update, rules));
+        .filter(|update| is_valid_ordering_with_buffer(update, rules, &mut pages_buffer));
 
     let middle_pages = valid_updates.map(|update| get_middle_page(update));
 
     middle_pages.sum()
 }
 
+// same as `solve_part_1`, but consumes updates one by one, without requiring
+// all of them to be stored up front
+#[allow(clippy::implicit_hasher)]
+#[must_use]
+pub fn sum_valid_middles<I: Iterator<Item = Vec<usize>>>(
+    rules: &HashMap<usize, HashSet<usize>>,
+    updates: I,
+) -> usize {
+    let mut pages_buffer = HashSet::new();
+
+    updates
+        .filter(|update| is_valid_ordering_with_buffer(update, rules, &mut pages_buffer))
+        .map(|update| get_middle_page(&update))
+        .sum()
+}
+
 #[must_use]
 pub fn solve_part_2(p: &Problem) -> usize {
     let Problem { rules, updates } = p;
@@ -221,19 +373,64 @@ mod tests {
                     vec![97, 13, 75, 29, 47],
                 ]
             }
-        )
+        );
     }
 
     #[test]
     fn test_is_valid_ordering() {
         let Problem { rules, updates } = TEST_INPUT.parse().unwrap();
 
-        assert_eq!(is_valid_ordering(&updates[0], &rules), true);
-        assert_eq!(is_valid_ordering(&updates[1], &rules), true);
-        assert_eq!(is_valid_ordering(&updates[2], &rules), true);
-        assert_eq!(is_valid_ordering(&updates[3], &rules), false);
-        assert_eq!(is_valid_ordering(&updates[4], &rules), false);
-        assert_eq!(is_valid_ordering(&updates[5], &rules), false);
+        assert!(is_valid_ordering(&updates[0], &rules));
+        assert!(is_valid_ordering(&updates[1], &rules));
+        assert!(is_valid_ordering(&updates[2], &rules));
+        assert!(!is_valid_ordering(&updates[3], &rules));
+        assert!(!is_valid_ordering(&updates[4], &rules));
+        assert!(!is_valid_ordering(&updates[5], &rules));
+    }
+
+    #[test]
+    fn test_is_valid_ordering_with_buffer() {
+        let Problem { rules, updates } = TEST_INPUT.parse().unwrap();
+
+        let mut pages_buffer = HashSet::new();
+
+        for update in &updates {
+            assert_eq!(
+                is_valid_ordering_with_buffer(update, &rules, &mut pages_buffer),
+                is_valid_ordering(update, &rules)
+            );
+        }
+
+        let sum_buffered: usize = updates
+            .iter()
+            .filter(|update| is_valid_ordering_with_buffer(update, &rules, &mut pages_buffer))
+            .map(|update| get_middle_page(update))
+            .sum();
+
+        let sum_allocating: usize = updates
+            .iter()
+            .filter(|update| is_valid_ordering(update, &rules))
+            .map(|update| get_middle_page(update))
+            .sum();
+
+        assert_eq!(sum_buffered, 143);
+        assert_eq!(sum_buffered, sum_allocating);
+    }
+
+    #[test]
+    fn test_is_fully_ordered() {
+        let Problem { mut rules, updates } = TEST_INPUT.parse().unwrap();
+
+        assert!(is_valid_ordering(&updates[0], &rules));
+        assert!(is_fully_ordered(&updates[0], &rules));
+        assert!(!is_fully_ordered(&updates[3], &rules));
+
+        // 75,47,61,53,29 stays valid without the 61|53 rule, but nothing
+        // orders 61 and 53 anymore
+        rules.get_mut(&61).unwrap().remove(&53);
+
+        assert!(is_valid_ordering(&updates[0], &rules));
+        assert!(!is_fully_ordered(&updates[0], &rules));
     }
 
     #[test]
@@ -243,6 +440,16 @@ mod tests {
         assert_eq!(get_middle_page(&[3, 6, 1, 4, 7, 9, 2]), 4);
     }
 
+    #[test]
+    fn test_get_middle_page_with() {
+        assert_eq!(get_middle_page_with(&[1, 2, 3, 4, 5], MiddleBias::Lower), 3);
+        assert_eq!(get_middle_page_with(&[1, 2, 3, 4, 5], MiddleBias::Upper), 3);
+        assert_eq!(get_middle_page_with(&[1, 2, 3, 4], MiddleBias::Lower), 2);
+        assert_eq!(get_middle_page_with(&[1, 2, 3, 4], MiddleBias::Upper), 3);
+        assert_eq!(get_middle_page_with(&[7, 9], MiddleBias::Lower), 7);
+        assert_eq!(get_middle_page_with(&[7, 9], MiddleBias::Upper), 9);
+    }
+
     #[test]
     fn test_solve_part_1() {
         let p: Problem = TEST_INPUT.parse().unwrap();
@@ -250,9 +457,71 @@ mod tests {
         assert_eq!(solve_part_1(&p), 143);
     }
 
+    #[test]
+    fn test_sum_edge_pages() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(p.sum_edge_pages(), (75 + 29) + (97 + 13) + (75 + 13));
+    }
+
+    #[test]
+    fn test_into_all_valid() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let expected = solve_part_1(&p) + solve_part_2(&p);
+        let p = p.into_all_valid();
+
+        assert!(p
+            .updates
+            .iter()
+            .all(|update| is_valid_ordering(update, &p.rules)));
+        assert_eq!(solve_part_1(&p), expected);
+        assert_eq!(solve_part_1(&p), 266);
+        assert_eq!(solve_part_2(&p), 0);
+    }
+
+    #[test]
+    fn test_all_pages() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(p.all_pages(), HashSet::from([13, 29, 47, 53, 61, 75, 97]));
+
+        let p: Problem = "1|2\n\n3,4".parse().unwrap();
+
+        assert_eq!(p.all_pages(), HashSet::from([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn test_most_constrained_page() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(p.most_constrained_page(), Some((97, 6)));
+
+        let p = Problem {
+            rules: HashMap::new(),
+            updates: vec![vec![1, 2]],
+        };
+
+        assert_eq!(p.most_constrained_page(), None);
+    }
+
+    #[test]
+    fn test_sum_valid_middles() {
+        let Problem { rules, updates } = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(sum_valid_middles(&rules, updates.into_iter()), 143);
+
+        let (_, updates) = TEST_INPUT.split_once("\n\n").unwrap();
+        let updates = updates
+            .lines()
+            .map(|l| l.split(',').map(|page| page.parse().unwrap()).collect());
+
+        assert_eq!(sum_valid_middles(&rules, updates), 143);
+    }
+
     #[test]
     fn test_page_move() {
-        let update = vec![1, 2, 3, 4, 5, 6];
+        let update = [1, 2, 3, 4, 5, 6];
         let current_page_ptr = 3;
         let pages_that_must_be_moved_after_current_page = vec![2, 3];
         let count_of_pages_to_move = pages_that_must_be_moved_after_current_page.len();
@@ -283,6 +552,23 @@ mod tests {
         assert_eq!(get_middle_page(&fix_ordering(&updates[5], &rules)), 47);
     }
 
+    #[test]
+    fn test_fix_distance() {
+        let Problem { rules, updates } = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(page_cmp(97, 75, &rules), Ordering::Less);
+        assert_eq!(page_cmp(75, 97, &rules), Ordering::Greater);
+
+        assert_eq!(fix_distance(&updates[0], &rules), 0);
+        assert!(fix_distance(&updates[3], &rules) > 0);
+        // 75,97,47,61,53 -> 97,75,47,61,53
+        assert_eq!(fix_distance(&updates[3], &rules), 1);
+        // 61,13,29 -> 61,29,13
+        assert_eq!(fix_distance(&updates[4], &rules), 1);
+        // 97,13,75,29,47 -> 97,75,47,29,13
+        assert_eq!(fix_distance(&updates[5], &rules), 4);
+    }
+
     #[test]
     fn test_solve_part_2() {
         let p: Problem = TEST_INPUT.parse().unwrap();
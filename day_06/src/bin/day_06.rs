@@ -1,4 +1,4 @@
-use std::{env, fs};
+use std::{env, fs, time::Instant};
 
 use anyhow::Context;
 use day_06::{solve_part_1, solve_part_2, Problem};
@@ -8,8 +8,13 @@ fn main() -> Result<(), anyhow::Error> {
     let input = fs::read_to_string(input_path)?;
     let p: Problem = input.parse()?;
 
-    println!("Part 1: {}", solve_part_1(&p));
-    println!("Part 2: {}", solve_part_2(&p));
+    let start = Instant::now();
+    let part_1 = solve_part_1(&p);
+    println!("Part 1: {part_1} ({:?})", start.elapsed());
+
+    let start = Instant::now();
+    let part_2 = solve_part_2(&p);
+    println!("Part 2: {part_2} ({:?})", start.elapsed());
 
     Ok(())
 }
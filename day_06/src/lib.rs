@@ -1,4 +1,8 @@
-use std::{collections::HashSet, str::FromStr};
+use anyhow::{anyhow, bail, ensure};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
 //   x
 //   |
@@ -6,23 +10,26 @@ use std::{collections::HashSet, str::FromStr};
 //   |
 //   v
 
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 pub struct Problem {
     map_height: usize,
     map_width: usize,
     obstacles: HashSet<(usize, usize)>,
     starting_position: (usize, usize),
+    starting_direction: Direction,
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-enum Direction {
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
     Up,
     Right,
     Down,
     Left,
 }
 
-fn next_direction(d: &Direction) -> Direction {
+// the guard always turns right
+#[must_use]
+pub fn next_direction(d: Direction) -> Direction {
     use Direction::{Down, Left, Right, Up};
 
     match d {
@@ -33,100 +40,412 @@ fn next_direction(d: &Direction) -> Direction {
     }
 }
 
-fn get_movement_delta(d: &Direction) -> (isize, isize) {
+// the guard turning left instead
+#[must_use]
+pub fn left_direction(d: Direction) -> Direction {
     use Direction::{Down, Left, Right, Up};
 
     match d {
-        Up => (-1, 0),
-        Right => (0, 1),
-        Down => (1, 0),
-        Left => (0, -1),
+        Up => Left,
+        Left => Down,
+        Down => Right,
+        Right => Up,
     }
 }
 
-impl FromStr for Problem {
-    type Err = anyhow::Error;
+// Which way the guard turns when running into an obstacle; `Right` is the
+// regular puzzle
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TurnRule {
+    #[default]
+    Right,
+    Left,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut starting_position = (0, 0);
+fn turn(d: Direction, turn_rule: TurnRule) -> Direction {
+    match turn_rule {
+        TurnRule::Right => next_direction(d),
+        TurnRule::Left => left_direction(d),
+    }
+}
 
-        let map_height = s.lines().count();
-        let map_width = s.lines().next().unwrap().chars().count();
+// How the guard moves in each `Direction`: `Orthogonal` is the regular
+// puzzle, `Diagonal` rotates every movement vector 45 degrees clockwise (so
+// `Up` becomes up-right), while turns still happen 90 degrees at a time
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Movement {
+    #[default]
+    Orthogonal,
+    Diagonal,
+}
 
-        let obstacles = s
-            .lines()
-            .enumerate()
-            .flat_map(|(x, l)| {
-                l.chars()
-                    .enumerate()
-                    .filter_map(|(y, c)| match c {
-                        '^' => {
-                            starting_position = (x, y);
-                            None
-                        }
-                        '.' => None,
-                        '#' => Some((x, y)),
-                        _ => unreachable!("Unknown map symbol"),
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect::<HashSet<_>>();
+// `(dx, dy)` of a single regular (orthogonal) step in direction `d`
+#[must_use]
+pub fn movement_delta(d: Direction) -> (isize, isize) {
+    get_movement_delta(d, Movement::Orthogonal)
+}
+
+fn get_movement_delta(d: Direction, movement: Movement) -> (isize, isize) {
+    use Direction::{Down, Left, Right, Up};
+
+    match movement {
+        Movement::Orthogonal => match d {
+            Up => (-1, 0),
+            Right => (0, 1),
+            Down => (1, 0),
+            Left => (0, -1),
+        },
+        Movement::Diagonal => match d {
+            Up => (-1, 1),
+            Right => (1, 1),
+            Down => (1, -1),
+            Left => (-1, -1),
+        },
+    }
+}
+
+impl Problem {
+    /// Builds the problem from an already split grid of map symbols.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the grid is empty, if its rows differ in length,
+    /// if it contains anything other than `.`, `#` and the guard, or if it
+    /// doesn't have exactly one guard (`^`, `>`, `v` or `<`) on it.
+    pub fn from_grid(grid: &[Vec<char>]) -> Result<Problem, anyhow::Error> {
+        let mut starting_position = None;
+        let mut obstacles = HashSet::new();
+
+        let map_height = grid.len();
+        let map_width = grid.first().ok_or_else(|| anyhow!("Empty map"))?.len();
+
+        for (x, row) in grid.iter().enumerate() {
+            ensure!(
+                row.len() == map_width,
+                "Row {x} is {} long, expected {map_width}",
+                row.len()
+            );
+
+            for (y, c) in row.iter().enumerate() {
+                let direction = match c {
+                    '.' => continue,
+                    '#' => {
+                        obstacles.insert((x, y));
+                        continue;
+                    }
+                    '^' => Direction::Up,
+                    '>' => Direction::Right,
+                    'v' => Direction::Down,
+                    '<' => Direction::Left,
+                    _ => bail!("Unknown map symbol '{c}' at {:?}", (x, y)),
+                };
+
+                ensure!(
+                    starting_position.is_none(),
+                    "More than one starting position found"
+                );
+
+                starting_position = Some(((x, y), direction));
+            }
+        }
+
+        let (starting_position, starting_direction) =
+            starting_position.ok_or_else(|| anyhow!("no starting position found"))?;
 
         Ok(Problem {
             map_height,
             map_width,
             obstacles,
             starting_position,
+            starting_direction,
+        })
+    }
+
+    /// Builds the problem from explicit obstacle coordinates, with the guard
+    /// facing up.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the map is empty, or if the starting position or
+    /// any of the obstacles lies outside of the map, or if the guard starts
+    /// on an obstacle.
+    pub fn from_obstacles(
+        map_height: usize,
+        map_width: usize,
+        obstacles: &[(usize, usize)],
+        starting_position: (usize, usize),
+    ) -> Result<Problem, anyhow::Error> {
+        let within_map = |(x, y): (usize, usize)| x < map_height && y < map_width;
+
+        ensure!(map_height > 0 && map_width > 0, "Empty map");
+        ensure!(
+            within_map(starting_position),
+            "Starting position {starting_position:?} outside of the map"
+        );
+
+        if let Some(obstacle) = obstacles.iter().find(|obstacle| !within_map(**obstacle)) {
+            bail!("Obstacle {obstacle:?} outside of the map");
+        }
+
+        let obstacles: HashSet<_> = obstacles.iter().copied().collect();
+
+        ensure!(
+            !obstacles.contains(&starting_position),
+            "Guard starts on an obstacle"
+        );
+
+        Ok(Problem {
+            map_height,
+            map_width,
+            obstacles,
+            starting_position,
+            starting_direction: Direction::Up,
+        })
+    }
+
+    // inclusive on both corners
+    pub fn add_obstacle_rect(&mut self, top_left: (usize, usize), bottom_right: (usize, usize)) {
+        for x in top_left.0..=bottom_right.0 {
+            for y in top_left.1..=bottom_right.1 {
+                self.obstacles.insert((x, y));
+            }
+        }
+    }
+}
+
+impl FromStr for Problem {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid = s
+            .lines()
+            .map(|l| l.chars().collect())
+            .collect::<Vec<Vec<char>>>();
+
+        Problem::from_grid(&grid)
+    }
+}
+
+// a single move of the guard - either a step forward or a turn in place
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Move {
+    Forward {
+        from: (usize, usize),
+        to: (usize, usize),
+        // direction the guard faces while stepping
+        direction: Direction,
+    },
+    Turn,
+}
+
+impl Move {
+    fn destination(self) -> Option<(usize, usize)> {
+        match self {
+            Move::Forward { to, .. } => Some(to),
+            Move::Turn => None,
+        }
+    }
+}
+
+// The guard's moves, ending once the guard leaves the map or is found stuck in
+// a loop (`looped`) - the latter when running into the same obstacle, facing
+// the same direction, for the second time
+struct Patrol<'a> {
+    obstacles: &'a HashSet<(usize, usize)>,
+    map_height: usize,
+    map_width: usize,
+    movement: Movement,
+    turn_rule: TurnRule,
+    position: (usize, usize),
+    direction: Direction,
+    collisions: HashSet<((usize, usize), Direction)>,
+    looped: bool,
+}
+
+impl<'a> Patrol<'a> {
+    fn new(
+        starting_position: (usize, usize),
+        starting_direction: Direction,
+        obstacles: &'a HashSet<(usize, usize)>,
+        map_height: usize,
+        map_width: usize,
+    ) -> Self {
+        Patrol {
+            obstacles,
+            map_height,
+            map_width,
+            movement: Movement::default(),
+            turn_rule: TurnRule::default(),
+            position: starting_position,
+            direction: starting_direction,
+            collisions: HashSet::new(),
+            looped: false,
+        }
+    }
+
+    fn from_problem(p: &'a Problem) -> Self {
+        Patrol::new(
+            p.starting_position,
+            p.starting_direction,
+            &p.obstacles,
+            p.map_height,
+            p.map_width,
+        )
+    }
+}
+
+impl Iterator for Patrol<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let movement_delta = get_movement_delta(self.direction, self.movement);
+
+        let (Some(next_x), Some(next_y)) = (
+            self.position.0.checked_add_signed(movement_delta.0),
+            self.position.1.checked_add_signed(movement_delta.1),
+        ) else {
+            // out of map
+            return None;
+        };
+
+        if next_x >= self.map_height || next_y >= self.map_width {
+            // out of map
+            return None;
+        }
+
+        if self.obstacles.contains(&(next_x, next_y)) {
+            if !self.collisions.insert(((next_x, next_y), self.direction)) {
+                self.looped = true;
+                return None;
+            }
+
+            // occupied space, rotate
+            self.direction = turn(self.direction, self.turn_rule);
+            return Some(Move::Turn);
+        }
+
+        let from = self.position;
+        self.position = (next_x, next_y);
+
+        Some(Move::Forward {
+            from,
+            to: self.position,
+            direction: self.direction,
         })
     }
 }
 
+// cells visited by the guard, or `None` if the guard gets stuck in a loop
 fn walk_maze(
     starting_position: &(usize, usize),
+    starting_direction: Direction,
     obstacles: &HashSet<(usize, usize)>,
     map_height: usize,
     map_width: usize,
-) -> usize {
+    movement: Movement,
+) -> Option<HashSet<(usize, usize)>> {
+    let path = walk_maze_path_with(
+        starting_position,
+        starting_direction,
+        obstacles,
+        map_height,
+        map_width,
+        movement,
+    )?;
+
+    Some(path.into_iter().map(|(position, _)| position).collect())
+}
+
+// same as `walk_maze`, but also gives up (returning `None`) if the guard is
+// still on the map after `max_steps` steps, where both moving forward and
+// turning count as a step
+fn walk_maze_capped(
+    starting_position: &(usize, usize),
+    starting_direction: Direction,
+    obstacles: &HashSet<(usize, usize)>,
+    map_height: usize,
+    map_width: usize,
+    movement: Movement,
+    max_steps: usize,
+) -> Option<HashSet<(usize, usize)>> {
+    let mut patrol = Patrol {
+        movement,
+        ..Patrol::new(
+            *starting_position,
+            starting_direction,
+            obstacles,
+            map_height,
+            map_width,
+        )
+    };
+
     let mut visited_spaces = HashSet::from([*starting_position]);
-    let mut current_position = *starting_position;
-    let mut movement_direction = Direction::Up;
-
-    loop {
-        let movement_delta = get_movement_delta(&movement_direction);
-
-        // TODO: replace if-let-else below with following let-else:
-        // let (Some(next_x), Some(next_y)) = (
-        //     current_position.0.checked_add_signed(movement_delta.0),
-        //     current_position.1.checked_add_signed(movement_delta.1),
-        // ) else {
-        //     // out of map
-        //     break;
-        // };
-
-        if let (Some(next_x), Some(next_y)) = (
-            current_position.0.checked_add_signed(movement_delta.0),
-            current_position.1.checked_add_signed(movement_delta.1),
-        ) {
-            if next_x >= map_height || next_y >= map_width {
-                // out of map
-                break;
-            }
+    let mut steps = 0;
 
-            if obstacles.contains(&(next_x, next_y)) {
-                // occupied space, rotate
-                movement_direction = next_direction(&movement_direction);
-                continue;
-            }
+    // leaving the map takes a step too, so running out of steps while still on
+    // the map (even before the first one) means giving up
+    while steps < max_steps {
+        let Some(m) = patrol.next() else {
+            return (!patrol.looped).then_some(visited_spaces);
+        };
 
-            current_position = (next_x, next_y);
-            visited_spaces.insert(current_position);
-        } else {
-            // out of map
-            break;
-        }
+        steps += 1;
+        visited_spaces.extend(m.destination());
     }
 
-    visited_spaces.len()
+    None
+}
+
+/// Every position the guard steps onto, in order, starting with the starting
+/// position and including revisits, along with the direction the guard was
+/// facing when stepping onto it; `None` if the guard gets stuck in a loop.
+#[allow(clippy::implicit_hasher)]
+#[must_use]
+pub fn walk_maze_path(
+    starting_position: &(usize, usize),
+    starting_direction: Direction,
+    obstacles: &HashSet<(usize, usize)>,
+    map_height: usize,
+    map_width: usize,
+) -> Option<Vec<((usize, usize), Direction)>> {
+    walk_maze_path_with(
+        starting_position,
+        starting_direction,
+        obstacles,
+        map_height,
+        map_width,
+        Movement::default(),
+    )
+}
+
+// same as `walk_maze_path`, but with the guard moving according to `movement`
+fn walk_maze_path_with(
+    starting_position: &(usize, usize),
+    starting_direction: Direction,
+    obstacles: &HashSet<(usize, usize)>,
+    map_height: usize,
+    map_width: usize,
+    movement: Movement,
+) -> Option<Vec<((usize, usize), Direction)>> {
+    let mut patrol = Patrol {
+        movement,
+        ..Patrol::new(
+            *starting_position,
+            starting_direction,
+            obstacles,
+            map_height,
+            map_width,
+        )
+    };
+
+    let mut path = vec![(*starting_position, starting_direction)];
+
+    path.extend(patrol.by_ref().filter_map(|m| match m {
+        Move::Forward { to, direction, .. } => Some((to, direction)),
+        Move::Turn => None,
+    }));
+
+    (!patrol.looped).then_some(path)
 }
 
 fn walk_maze_and_check_for_loop(
@@ -136,117 +455,414 @@ fn walk_maze_and_check_for_loop(
     map_height: usize,
     map_width: usize,
 ) -> bool {
-    let mut collided_obstacles: HashSet<(usize, usize, Direction)> = HashSet::new();
-    let mut current_position = *starting_position;
-    let mut movement_direction = starting_direction;
+    let mut patrol = Patrol::new(
+        *starting_position,
+        starting_direction,
+        obstacles,
+        map_height,
+        map_width,
+    );
 
-    loop {
-        let movement_delta = get_movement_delta(&movement_direction);
+    while patrol.next().is_some() {}
 
-        if let (Some(next_x), Some(next_y)) = (
-            current_position.0.checked_add_signed(movement_delta.0),
-            current_position.1.checked_add_signed(movement_delta.1),
-        ) {
-            if next_x >= map_height || next_y >= map_width {
-                // out of map
-                break;
+    patrol.looped
+}
+
+// whether the guard ever steps back onto the starting position after leaving
+// it (a guard stuck in a loop elsewhere never does)
+#[must_use]
+pub fn returns_to_start(p: &Problem) -> bool {
+    Patrol::from_problem(p).any(|m| m.destination() == Some(p.starting_position))
+}
+
+// whether placing a single extra obstruction at `pos` traps the guard in a
+// loop; positions outside of the map and the guard's own starting position
+// can't hold an obstruction
+#[must_use]
+pub fn obstruction_causes_loop(p: &Problem, pos: (usize, usize)) -> bool {
+    let Problem {
+        map_height,
+        map_width,
+        obstacles,
+        starting_position,
+        starting_direction,
+    } = p;
+
+    if pos.0 >= *map_height || pos.1 >= *map_width || pos == *starting_position {
+        return false;
+    }
+
+    let expanded_obstacles = {
+        let mut temp = obstacles.clone();
+        temp.insert(pos);
+        temp
+    };
+
+    walk_maze_and_check_for_loop(
+        starting_position,
+        *starting_direction,
+        &expanded_obstacles,
+        *map_height,
+        *map_width,
+    )
+}
+
+/// Number of distinct cells the guard visits before leaving the map.
+///
+/// # Panics
+///
+/// Panics if the guard gets stuck in a loop.
+#[must_use]
+pub fn solve_part_1(p: &Problem) -> usize {
+    solve_part_1_with_movement(p, Movement::default()).expect("Guard should leave the map")
+}
+
+/// Same as [`solve_part_1`], but protects against maps the guard never
+/// leaves.
+///
+/// # Errors
+///
+/// Returns an error if the guard hasn't left the map within `max_steps` steps
+/// (moving forward and turning both count as a step), or gets stuck in a
+/// loop.
+pub fn solve_part_1_capped(p: &Problem, max_steps: usize) -> Result<usize, anyhow::Error> {
+    let Problem {
+        map_height,
+        map_width,
+        obstacles,
+        starting_position,
+        starting_direction,
+    } = p;
+
+    walk_maze_capped(
+        starting_position,
+        *starting_direction,
+        obstacles,
+        *map_height,
+        *map_width,
+        Movement::default(),
+        max_steps,
+    )
+    .map(|visited_spaces| visited_spaces.len())
+    .ok_or_else(|| anyhow!("Guard didn't leave the map within {max_steps} steps"))
+}
+
+/// Number of moves the guard makes before leaving the map, counting every
+/// revisit of an already visited cell (turning in place isn't a move).
+///
+/// # Panics
+///
+/// Panics if the guard gets stuck in a loop.
+#[must_use]
+pub fn total_steps(p: &Problem) -> usize {
+    let mut patrol = Patrol::from_problem(p);
+
+    let steps = patrol
+        .by_ref()
+        .filter(|m| matches!(m, Move::Forward { .. }))
+        .count();
+
+    assert!(!patrol.looped, "Guard should leave the map");
+
+    steps
+}
+
+// number of 90 degree turns and of (non-empty) straight runs in the patrol, or
+// `None` if the guard gets stuck in a loop
+fn count_turns_and_segments(p: &Problem) -> Option<(usize, usize)> {
+    let mut patrol = Patrol::from_problem(p);
+    let mut turns = 0;
+    let mut segments = 0;
+    let mut moved_since_turn = false;
+
+    for m in patrol.by_ref() {
+        match m {
+            Move::Forward { .. } => {
+                moved_since_turn = true;
             }
+            Move::Turn => {
+                turns += 1;
 
-            if obstacles.contains(&(next_x, next_y)) {
-                // check if it is repeated collision which indicates a loop
-                if collided_obstacles.contains(&(next_x, next_y, movement_direction.clone())) {
-                    return true;
+                if moved_since_turn {
+                    segments += 1;
+                    moved_since_turn = false;
                 }
+            }
+        }
+    }
 
-                // first store the collision
-                collided_obstacles.insert((next_x, next_y, movement_direction.clone()));
+    if moved_since_turn {
+        segments += 1;
+    }
 
-                // then rotate
-                movement_direction = next_direction(&movement_direction);
-                continue;
-            }
+    (!patrol.looped).then_some((turns, segments))
+}
 
-            current_position = (next_x, next_y);
-        } else {
-            // out of map
-            break;
+/// Number of times the guard turns before leaving the map, turning twice in a
+/// dead end counting as two turns.
+///
+/// # Panics
+///
+/// Panics if the guard gets stuck in a loop.
+#[must_use]
+pub fn turn_count(p: &Problem) -> usize {
+    count_turns_and_segments(p)
+        .expect("Guard should leave the map")
+        .0
+}
+
+/// Number of maximal straight runs the guard walks - `turn_count + 1`, unless
+/// the guard turns more than once in place, or right at the start.
+///
+/// # Panics
+///
+/// Panics if the guard gets stuck in a loop.
+#[must_use]
+pub fn segment_count(p: &Problem) -> usize {
+    count_turns_and_segments(p)
+        .expect("Guard should leave the map")
+        .1
+}
+
+// `None` if the guard gets stuck in a loop
+#[must_use]
+pub fn solve_part_1_with_movement(p: &Problem, movement: Movement) -> Option<usize> {
+    let Problem {
+        map_height,
+        map_width,
+        obstacles,
+        starting_position,
+        starting_direction,
+    } = p;
+
+    walk_maze(
+        starting_position,
+        *starting_direction,
+        obstacles,
+        *map_height,
+        *map_width,
+        movement,
+    )
+    .map(|visited_spaces| visited_spaces.len())
+}
+
+// like `solve_part_1`, but with the guard turning according to `turn_rule`;
+// `None` if the guard gets stuck in a loop
+#[must_use]
+pub fn solve_part_1_with_turn_rule(p: &Problem, turn_rule: TurnRule) -> Option<usize> {
+    let mut patrol = Patrol {
+        turn_rule,
+        ..Patrol::from_problem(p)
+    };
+
+    let mut visited_spaces = HashSet::from([p.starting_position]);
+
+    visited_spaces.extend(patrol.by_ref().filter_map(Move::destination));
+
+    (!patrol.looped).then_some(visited_spaces.len())
+}
+
+// number of distinct cells visited by a guard starting (facing up) from each
+// empty cell other than the actual starting position; a guard stuck in a loop
+// gets all the cells visited before the loop closes
+#[must_use]
+pub fn visited_counts_from_all_starts(p: &Problem) -> HashMap<(usize, usize), usize> {
+    let Problem {
+        map_height,
+        map_width,
+        obstacles,
+        starting_position,
+        ..
+    } = p;
+
+    let mut visited_counts = HashMap::new();
+
+    for start in (0..*map_height).flat_map(|x| (0..*map_width).map(move |y| (x, y))) {
+        if start == *starting_position || obstacles.contains(&start) {
+            continue;
         }
+
+        let visited_spaces =
+            walk_maze_until_exit_or_loop(start, Direction::Up, obstacles, *map_height, *map_width);
+
+        visited_counts.insert(start, visited_spaces.len());
     }
 
-    false
+    visited_counts
 }
 
+// cells visited by the guard until leaving the map, or until stuck in a loop
+fn walk_maze_until_exit_or_loop(
+    starting_position: (usize, usize),
+    starting_direction: Direction,
+    obstacles: &HashSet<(usize, usize)>,
+    map_height: usize,
+    map_width: usize,
+) -> HashSet<(usize, usize)> {
+    let patrol = Patrol::new(
+        starting_position,
+        starting_direction,
+        obstacles,
+        map_height,
+        map_width,
+    );
+
+    let mut visited_spaces = HashSet::from([starting_position]);
+
+    visited_spaces.extend(patrol.filter_map(Move::destination));
+
+    visited_spaces
+}
+
+// empty cells the guard never steps onto, neither on the original patrol nor
+// with any single obstruction added to the map
 #[must_use]
-pub fn solve_part_1(p: &Problem) -> usize {
+pub fn always_unvisited(p: &Problem) -> HashSet<(usize, usize)> {
     let Problem {
         map_height,
         map_width,
         obstacles,
         starting_position,
+        ..
     } = p;
 
-    walk_maze(starting_position, obstacles, *map_height, *map_width)
+    let mut visited_spaces = HashSet::from([*starting_position]);
+
+    // as in `loop_positions`, only obstructions on the original path reroute
+    // the guard, and each walk resumes from the state right before the
+    // obstruction's first visit - the cells before it are on the path already
+    let mut checked_positions = HashSet::from([*starting_position]);
+
+    for m in Patrol::from_problem(p) {
+        let Move::Forward {
+            from,
+            to,
+            direction,
+        } = m
+        else {
+            continue;
+        };
+
+        visited_spaces.insert(to);
+
+        if !checked_positions.insert(to) {
+            continue;
+        }
+
+        let expanded_obstacles = {
+            let mut temp = obstacles.clone();
+            temp.insert(to);
+            temp
+        };
+
+        visited_spaces.extend(walk_maze_until_exit_or_loop(
+            from,
+            direction,
+            &expanded_obstacles,
+            *map_height,
+            *map_width,
+        ));
+    }
+
+    (0..*map_height)
+        .flat_map(|x| (0..*map_width).map(move |y| (x, y)))
+        .filter(|cell| !obstacles.contains(cell) && !visited_spaces.contains(cell))
+        .collect()
 }
 
+/// Cells visited by the guard (including the starting one), sorted by
+/// `(x, y)`.
+///
+/// # Panics
+///
+/// Panics if the guard gets stuck in a loop.
 #[must_use]
-pub fn solve_part_2(p: &Problem) -> usize {
+pub fn visited_sorted(p: &Problem) -> Vec<(usize, usize)> {
     let Problem {
         map_height,
         map_width,
         obstacles,
         starting_position,
+        starting_direction,
     } = p;
 
-    let mut loop_inducing_obstacles: HashSet<(usize, usize)> = HashSet::new();
+    let mut visited_spaces: Vec<_> = walk_maze(
+        starting_position,
+        *starting_direction,
+        obstacles,
+        *map_height,
+        *map_width,
+        Movement::Orthogonal,
+    )
+    .expect("Guard should leave the map")
+    .into_iter()
+    .collect();
 
-    let mut current_position = *starting_position;
-    let mut movement_direction = Direction::Up;
+    visited_spaces.sort_unstable();
 
-    loop {
-        let movement_delta = get_movement_delta(&movement_direction);
+    visited_spaces
+}
 
-        if let (Some(next_x), Some(next_y)) = (
-            current_position.0.checked_add_signed(movement_delta.0),
-            current_position.1.checked_add_signed(movement_delta.1),
-        ) {
-            if next_x >= *map_height || next_y >= *map_width {
-                // out of map
-                break;
-            }
+// positions where a single added obstruction traps the guard in a loop, sorted
+#[must_use]
+pub fn loop_positions(p: &Problem) -> Vec<(usize, usize)> {
+    let Problem {
+        map_height,
+        map_width,
+        obstacles,
+        starting_position,
+        ..
+    } = p;
 
-            if obstacles.contains(&(next_x, next_y)) {
-                // occupied space, rotate
-                movement_direction = next_direction(&movement_direction);
-                continue;
-            }
+    // an obstruction off the guard's original path is never run into, so only
+    // the visited cells (other than the start) are worth checking; up to its
+    // first visit of a cell, the guard walks the same with or without an
+    // obstruction there, so each check resumes the walk from the guard's state
+    // right before that visit instead of starting over - for a path of length
+    // `L` this skips on average half of each of the (up to `L`) walks, same
+    // O(L^2) overall, but roughly twice as fast
+    let mut checked_positions = HashSet::from([*starting_position]);
+    let mut loop_inducing_obstacles = vec![];
 
-            // check if placing an obstacle in front of guard leads to a loop
-            let expanded_obstacles = {
-                let mut temp = obstacles.clone();
-                temp.insert((next_x, next_y));
-                temp
-            };
+    for m in Patrol::from_problem(p) {
+        let Move::Forward {
+            from,
+            to,
+            direction,
+        } = m
+        else {
+            continue;
+        };
 
-            if !(next_x == starting_position.0 && next_y == starting_position.1)
-                && walk_maze_and_check_for_loop(
-                    &current_position,
-                    movement_direction.clone(),
-                    &expanded_obstacles,
-                    *map_height,
-                    *map_width,
-                )
-            {
-                loop_inducing_obstacles.insert((next_x, next_y));
-            }
+        if !checked_positions.insert(to) {
+            continue;
+        }
 
-            current_position = (next_x, next_y);
-        } else {
-            // out of map
-            break;
+        let expanded_obstacles = {
+            let mut temp = obstacles.clone();
+            temp.insert(to);
+            temp
+        };
+
+        if walk_maze_and_check_for_loop(
+            &from,
+            direction,
+            &expanded_obstacles,
+            *map_height,
+            *map_width,
+        ) {
+            loop_inducing_obstacles.push(to);
         }
     }
 
-    loop_inducing_obstacles.len()
+    loop_inducing_obstacles.sort_unstable();
+
+    loop_inducing_obstacles
+}
+
+#[must_use]
+pub fn solve_part_2(p: &Problem) -> usize {
+    loop_positions(p).len()
 }
 
 #[cfg(test)]
@@ -271,10 +887,126 @@ mod tests {
 
         assert_eq!(p.map_height, 10);
         assert_eq!(p.map_width, 10);
-        assert_eq!(p.obstacles.contains(&(0, 0)), false);
-        assert_eq!(p.obstacles.contains(&(0, 4)), true);
-        assert_eq!(p.obstacles.contains(&(6, 4)), false);
+        assert!(!p.obstacles.contains(&(0, 0)));
+        assert!(p.obstacles.contains(&(0, 4)));
+        assert!(!p.obstacles.contains(&(6, 4)));
         assert_eq!(p.starting_position, (6, 4));
+        assert_eq!(p.starting_direction, Direction::Up);
+    }
+
+    #[test]
+    fn test_problem_parsing_starting_direction() {
+        let p: Problem = TEST_INPUT.replace('^', ">").parse().unwrap();
+
+        assert_eq!(p.starting_position, (6, 4));
+        assert_eq!(p.starting_direction, Direction::Right);
+        // walks straight right off the map
+        assert_eq!(solve_part_1(&p), 6);
+
+        let p: Problem = TEST_INPUT.replace('^', "v").parse().unwrap();
+
+        assert_eq!(p.starting_direction, Direction::Down);
+
+        let p: Problem = TEST_INPUT.replace('^', "<").parse().unwrap();
+
+        assert_eq!(p.starting_direction, Direction::Left);
+    }
+
+    #[test]
+    fn test_problem_from_grid() {
+        let grid: Vec<Vec<char>> = TEST_INPUT.lines().map(|l| l.chars().collect()).collect();
+
+        let p = Problem::from_grid(&grid).unwrap();
+
+        assert_eq!(p, TEST_INPUT.parse().unwrap());
+        assert_eq!(solve_part_1(&p), 41);
+
+        assert!(Problem::from_grid(&[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_without_starting_position() {
+        let input = TEST_INPUT.replace('^', ".");
+
+        assert!(input.parse::<Problem>().is_err());
+    }
+
+    #[test]
+    fn test_problem_from_grid_errors() {
+        let grid = |s: &str| -> Vec<Vec<char>> { s.lines().map(|l| l.chars().collect()).collect() };
+
+        // unknown symbol
+        assert!(Problem::from_grid(&grid("..\n.^\n.x")).is_err());
+        // ragged rows
+        assert!(Problem::from_grid(&grid("...\n.^\n...")).is_err());
+        assert!(Problem::from_grid(&grid("..\n.^.")).is_err());
+        // more than one guard
+        assert!(Problem::from_grid(&grid("^.\n.^")).is_err());
+        assert!(Problem::from_grid(&grid("^.\n.>")).is_err());
+
+        assert!(Problem::from_grid(&grid("#.\n.^")).is_ok());
+    }
+
+    #[test]
+    fn test_problem_from_obstacles() {
+        let p = Problem::from_obstacles(
+            10,
+            10,
+            &[
+                (0, 4),
+                (1, 9),
+                (3, 2),
+                (4, 7),
+                (6, 1),
+                (7, 8),
+                (8, 0),
+                (9, 6),
+            ],
+            (6, 4),
+        )
+        .unwrap();
+
+        assert_eq!(p, TEST_INPUT.parse().unwrap());
+        assert_eq!(solve_part_1(&p), 41);
+
+        assert!(Problem::from_obstacles(0, 10, &[], (0, 0)).is_err());
+        assert!(Problem::from_obstacles(10, 10, &[], (10, 0)).is_err());
+        assert!(Problem::from_obstacles(10, 10, &[(0, 10)], (0, 0)).is_err());
+        assert!(Problem::from_obstacles(10, 10, &[(6, 4)], (6, 4)).is_err());
+    }
+
+    #[test]
+    fn test_add_obstacle_rect() {
+        let mut p: Problem = "\
+....
+.^..
+...."
+            .parse()
+            .unwrap();
+
+        assert_eq!(solve_part_1(&p), 2);
+
+        p.add_obstacle_rect((0, 0), (0, 3));
+
+        assert_eq!(p.obstacles.len(), 4);
+        assert_eq!(solve_part_1(&p), 3);
+
+        p.add_obstacle_rect((1, 2), (2, 3));
+
+        assert_eq!(p.obstacles.len(), 8);
+        assert_eq!(solve_part_1(&p), 2);
+    }
+
+    #[test]
+    fn test_next_direction() {
+        let mut d = Direction::Up;
+
+        for expected_delta in [(-1, 0), (0, 1), (1, 0), (0, -1)] {
+            assert_eq!(movement_delta(d), expected_delta);
+            d = next_direction(d);
+        }
+
+        assert_eq!(d, Direction::Up);
     }
 
     #[test]
@@ -285,103 +1017,432 @@ mod tests {
     }
 
     #[test]
-    fn test_walk_maze_and_check_for_loop() {
+    fn test_always_unvisited() {
+        let p: Problem = "\
+...
+...
+.^."
+        .parse()
+        .unwrap();
+
+        // a single obstruction makes the guard turn right at most once, so
+        // nothing to the left of it, or in the top-right corner, is reachable
+        assert_eq!(
+            always_unvisited(&p),
+            HashSet::from([(0, 0), (1, 0), (2, 0), (0, 2)])
+        );
+
         let p: Problem = TEST_INPUT.parse().unwrap();
 
+        let unvisited = always_unvisited(&p);
+
+        assert!(visited_sorted(&p)
+            .iter()
+            .all(|cell| !unvisited.contains(cell)));
+        assert!(unvisited.iter().all(|cell| !p.obstacles.contains(cell)));
+    }
+
+    #[test]
+    fn test_visited_counts_from_all_starts() {
+        let p: Problem = "\
+.#.
+.^.
+..."
+        .parse()
+        .unwrap();
+
+        let visited_counts = visited_counts_from_all_starts(&p);
+
+        assert_eq!(visited_counts.len(), 9 - 2);
+        assert!(!visited_counts.contains_key(&(0, 1)));
+        assert!(!visited_counts.contains_key(&p.starting_position));
+        assert_eq!(visited_counts[&(0, 0)], 1);
+        assert_eq!(visited_counts[&(2, 0)], 3);
+        // up to (1, 1), turn right at (0, 1) and leave through (1, 2)
+        assert_eq!(visited_counts[&(2, 1)], 3);
+
+        // the guard loops when starting below the top-left obstacle
+        let p: Problem = "\
+.#..
+...#
+#^..
+..#."
+            .parse()
+            .unwrap();
+
+        assert_eq!(visited_counts_from_all_starts(&p)[&(1, 1)], 4);
+    }
+
+    #[test]
+    fn test_walk_maze_path() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let path = walk_maze_path(
+            &p.starting_position,
+            p.starting_direction,
+            &p.obstacles,
+            p.map_height,
+            p.map_width,
+        )
+        .unwrap();
+
+        let positions = path
+            .iter()
+            .map(|(position, _)| *position)
+            .collect::<Vec<_>>();
+
+        assert_eq!(positions[..4], [(6, 4), (5, 4), (4, 4), (3, 4)]);
+        // bumps into (0, 4) and turns right
+        assert_eq!(positions[5..7], [(1, 4), (1, 5)]);
+        assert_eq!(path[5].1, Direction::Up);
+        assert_eq!(path[6].1, Direction::Right);
+        assert_eq!(path.len(), total_steps(&p) + 1);
+        assert_eq!(positions.iter().collect::<HashSet<_>>().len(), 41);
+    }
+
+    #[test]
+    fn test_visited_sorted() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let visited = visited_sorted(&p);
+
+        assert_eq!(visited.len(), 41);
+        assert!(visited.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(visited[0], (1, 4));
+        assert!(visited.contains(&p.starting_position));
+    }
+
+    #[test]
+    fn test_total_steps() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert!(total_steps(&p) >= solve_part_1(&p));
+        assert_eq!(total_steps(&p), 44);
+
+        let p: Problem = "\
+...
+.^.
+..."
+        .parse()
+        .unwrap();
+
+        assert_eq!(total_steps(&p), 1);
+    }
+
+    #[test]
+    fn test_segment_count() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(turn_count(&p), 10);
+        assert_eq!(segment_count(&p), turn_count(&p) + 1);
+
+        // turns right away, walks one cell right, then turns twice in the dead
+        // end before heading back
+        let p: Problem = "\
+.#.#
+.^.#
+.###"
+            .parse()
+            .unwrap();
+
+        assert_eq!(turn_count(&p), 3);
+        assert_eq!(segment_count(&p), 2);
+    }
+
+    #[test]
+    fn test_solve_part_1_capped() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(solve_part_1_capped(&p, 1000).unwrap(), 41);
+        assert!(solve_part_1_capped(&p, 10).is_err());
+        assert!(solve_part_1_capped(&p, 0).is_err());
+
+        let p: Problem = "\
+.#..
+...#
+#^..
+..#."
+            .parse()
+            .unwrap();
+
+        assert!(solve_part_1_capped(&p, 1000).is_err());
+        assert!(solve_part_1_capped(&p, usize::MAX).is_err());
+
+        // leaving the map right away still takes one step
+        let p: Problem = "#^#".parse().unwrap();
+
+        assert!(solve_part_1_capped(&p, 0).is_err());
+        assert_eq!(solve_part_1_capped(&p, 1).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_walk_maze_loop() {
+        let p: Problem = "\
+.#..
+...#
+#^..
+..#."
+            .parse()
+            .unwrap();
+
+        assert!(walk_maze_path(
+            &p.starting_position,
+            p.starting_direction,
+            &p.obstacles,
+            p.map_height,
+            p.map_width,
+        )
+        .is_none());
+        assert_eq!(solve_part_1_with_movement(&p, Movement::Orthogonal), None);
+        assert_eq!(solve_part_1_with_turn_rule(&p, TurnRule::Right), None);
+
+        let mut patrol = Patrol::from_problem(&p);
+
+        // four steps and four turns around the loop, then the step up to
+        // (1, 1) again, before running into (0, 1) facing up once more
+        assert_eq!(patrol.by_ref().count(), 9);
+        assert!(patrol.looped);
+    }
+
+    #[test]
+    fn test_walk_maze_immediate_exit() {
+        let p: Problem = "\
+..^..
+.....
+#...."
+            .parse()
+            .unwrap();
+
+        // guard leaves the map with the very first step, only the starting
+        // position is visited
         assert_eq!(
-            walk_maze_and_check_for_loop(
+            walk_maze(
                 &p.starting_position,
-                Direction::Up,
+                p.starting_direction,
                 &p.obstacles,
                 p.map_height,
-                p.map_width
-            ),
-            false
+                p.map_width,
+                Movement::Orthogonal
+            )
+            .unwrap()
+            .len(),
+            1
         );
+        assert_eq!(solve_part_1(&p), 1);
+    }
 
-        let mut obstacles_with_loop_1 = p.obstacles.clone();
-        obstacles_with_loop_1.insert((6, 3));
+    #[test]
+    fn test_solve_part_1_with_movement() {
+        let p: Problem = "\
+.....
+...#.
+..^.."
+            .parse()
+            .unwrap();
 
+        // straight up, out of the map
         assert_eq!(
-            walk_maze_and_check_for_loop(
-                &p.starting_position,
-                Direction::Up,
-                &obstacles_with_loop_1,
-                p.map_height,
-                p.map_width
-            ),
-            true
+            solve_part_1_with_movement(&p, Movement::Orthogonal),
+            Some(3)
         );
+        // blocked up-right right away, turns to down-right and leaves the map
+        assert_eq!(solve_part_1_with_movement(&p, Movement::Diagonal), Some(1));
+    }
 
-        let mut obstacles_with_loop_2 = p.obstacles.clone();
-        obstacles_with_loop_2.insert((7, 6));
+    #[test]
+    fn test_solve_part_1_with_turn_rule() {
+        let p: Problem = "\
+..#..
+#....
+.....
+..^..
+....."
+            .parse()
+            .unwrap();
 
+        let visited_with = |turn_rule| {
+            let patrol = Patrol {
+                turn_rule,
+                ..Patrol::from_problem(&p)
+            };
+
+            patrol
+                .filter_map(Move::destination)
+                .chain([p.starting_position])
+                .collect::<HashSet<_>>()
+        };
+
+        // up, then right out of the map
         assert_eq!(
-            walk_maze_and_check_for_loop(
-                &p.starting_position,
-                Direction::Up,
-                &obstacles_with_loop_2,
-                p.map_height,
-                p.map_width
-            ),
-            true
+            visited_with(TurnRule::Right),
+            HashSet::from([(3, 2), (2, 2), (1, 2), (1, 3), (1, 4)])
+        );
+        // up, then left into (1, 0), then down out of the map
+        assert_eq!(
+            visited_with(TurnRule::Left),
+            HashSet::from([(3, 2), (2, 2), (1, 2), (1, 1), (2, 1), (3, 1), (4, 1)])
         );
 
-        let mut obstacles_with_loop_3 = p.obstacles.clone();
-        obstacles_with_loop_3.insert((7, 7));
+        assert_eq!(solve_part_1_with_turn_rule(&p, TurnRule::Right), Some(5));
+        assert_eq!(solve_part_1_with_turn_rule(&p, TurnRule::Left), Some(7));
+
+        let p: Problem = TEST_INPUT.parse().unwrap();
 
         assert_eq!(
-            walk_maze_and_check_for_loop(
-                &p.starting_position,
-                Direction::Up,
-                &obstacles_with_loop_3,
-                p.map_height,
-                p.map_width
-            ),
-            true
+            solve_part_1_with_turn_rule(&p, TurnRule::default()),
+            Some(solve_part_1(&p))
         );
+    }
+
+    #[test]
+    fn test_left_direction() {
+        let mut d = Direction::Up;
+
+        for _ in 0..4 {
+            assert_eq!(next_direction(left_direction(d)), d);
+            d = left_direction(d);
+        }
+
+        assert_eq!(left_direction(Direction::Up), Direction::Left);
+    }
+
+    #[test]
+    fn test_walk_maze_and_check_for_loop() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert!(!walk_maze_and_check_for_loop(
+            &p.starting_position,
+            Direction::Up,
+            &p.obstacles,
+            p.map_height,
+            p.map_width
+        ));
+
+        let mut obstacles_with_loop_1 = p.obstacles.clone();
+        obstacles_with_loop_1.insert((6, 3));
+
+        assert!(walk_maze_and_check_for_loop(
+            &p.starting_position,
+            Direction::Up,
+            &obstacles_with_loop_1,
+            p.map_height,
+            p.map_width
+        ));
+
+        let mut obstacles_with_loop_2 = p.obstacles.clone();
+        obstacles_with_loop_2.insert((7, 6));
+
+        assert!(walk_maze_and_check_for_loop(
+            &p.starting_position,
+            Direction::Up,
+            &obstacles_with_loop_2,
+            p.map_height,
+            p.map_width
+        ));
+
+        let mut obstacles_with_loop_3 = p.obstacles.clone();
+        obstacles_with_loop_3.insert((7, 7));
+
+        assert!(walk_maze_and_check_for_loop(
+            &p.starting_position,
+            Direction::Up,
+            &obstacles_with_loop_3,
+            p.map_height,
+            p.map_width
+        ));
 
         let mut obstacles_with_loop_4 = p.obstacles.clone();
         obstacles_with_loop_4.insert((8, 1));
 
-        assert_eq!(
-            walk_maze_and_check_for_loop(
-                &p.starting_position,
-                Direction::Up,
-                &obstacles_with_loop_4,
-                p.map_height,
-                p.map_width
-            ),
-            true
-        );
+        assert!(walk_maze_and_check_for_loop(
+            &p.starting_position,
+            Direction::Up,
+            &obstacles_with_loop_4,
+            p.map_height,
+            p.map_width
+        ));
 
         let mut obstacles_with_loop_5 = p.obstacles.clone();
         obstacles_with_loop_5.insert((8, 3));
 
-        assert_eq!(
-            walk_maze_and_check_for_loop(
-                &p.starting_position,
-                Direction::Up,
-                &obstacles_with_loop_5,
-                p.map_height,
-                p.map_width
-            ),
-            true
-        );
+        assert!(walk_maze_and_check_for_loop(
+            &p.starting_position,
+            Direction::Up,
+            &obstacles_with_loop_5,
+            p.map_height,
+            p.map_width
+        ));
 
         let mut obstacles_with_loop_6 = p.obstacles.clone();
         obstacles_with_loop_6.insert((9, 7));
 
+        assert!(walk_maze_and_check_for_loop(
+            &p.starting_position,
+            Direction::Up,
+            &obstacles_with_loop_6,
+            p.map_height,
+            p.map_width
+        ));
+    }
+
+    #[test]
+    fn test_returns_to_start() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        // heading left along row 6 the guard crosses (6, 4) again
+        assert!(returns_to_start(&p));
+
+        let p: Problem = "\
+.....
+..^..
+....."
+            .parse()
+            .unwrap();
+
+        assert!(!returns_to_start(&p));
+
+        // looping around right above the starting position
+        let p: Problem = "\
+.#..
+...#
+#...
+..#.
+.^.."
+            .parse()
+            .unwrap();
+
+        assert!(!returns_to_start(&p));
+    }
+
+    #[test]
+    fn test_obstruction_causes_loop() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        for pos in [(6, 3), (7, 6), (7, 7), (8, 1), (8, 3), (9, 7)] {
+            assert!(obstruction_causes_loop(&p, pos));
+        }
+
+        assert!(!obstruction_causes_loop(&p, (0, 0)));
+        assert!(!obstruction_causes_loop(&p, (5, 4)));
+        assert!(!obstruction_causes_loop(&p, p.starting_position));
+        assert!(!obstruction_causes_loop(&p, (10, 3)));
+    }
+
+    #[test]
+    fn test_loop_positions() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
         assert_eq!(
-            walk_maze_and_check_for_loop(
-                &p.starting_position,
-                Direction::Up,
-                &obstacles_with_loop_6,
-                p.map_height,
-                p.map_width
-            ),
-            true
+            loop_positions(&p),
+            vec![(6, 3), (7, 6), (7, 7), (8, 1), (8, 3), (9, 7)]
         );
+
+        let every_position = (0..p.map_height)
+            .flat_map(|x| (0..p.map_width).map(move |y| (x, y)))
+            .filter(|position| obstruction_causes_loop(&p, *position))
+            .collect::<Vec<_>>();
+
+        assert_eq!(loop_positions(&p), every_position);
     }
 
     #[test]
@@ -389,5 +1450,13 @@ mod tests {
         let p: Problem = TEST_INPUT.parse().unwrap();
 
         assert_eq!(solve_part_2(&p), 6);
+
+        // same as re-running the whole walk for every visited cell
+        let naive_count = visited_sorted(&p)
+            .into_iter()
+            .filter(|position| obstruction_causes_loop(&p, *position))
+            .count();
+
+        assert_eq!(solve_part_2(&p), naive_count);
     }
 }
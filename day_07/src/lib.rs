@@ -1,10 +1,34 @@
-use anyhow::anyhow;
-use std::str::FromStr;
+use anyhow::{anyhow, ensure};
+use std::{fmt::Write, str::FromStr};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operator {
+    Add,
+    Multiply,
+    Concat,
+}
+
+impl Operator {
+    // `b_digits` is the number of digits of `b`, needed by `Concat`
+    fn apply(self, a: usize, b: usize, b_digits: u32) -> usize {
+        match self {
+            Operator::Add => a + b,
+            Operator::Multiply => a * b,
+            Operator::Concat => concat(a, b, b_digits),
+        }
+    }
+}
 
 #[derive(Debug, Eq, PartialEq)]
 struct OplessEquation {
     operands: Vec<usize>,
+    // number of digits of each operand, for cheap concatenation
+    operand_digits: Vec<u32>,
     result: usize,
+    // operators allowed by the (optional) `[...]` set following the result,
+    // e.g. `190 [+*|]: 10 19`, with `|` standing for concatenation; only used
+    // by `solve_part_1_per_line_ops`
+    operators: Vec<Operator>,
 }
 
 impl FromStr for OplessEquation {
@@ -15,14 +39,43 @@ impl FromStr for OplessEquation {
             .split_once(": ")
             .ok_or_else(|| anyhow!("Couldn't split on ': '"))?;
 
-        let operands = operands
+        let operands: Vec<usize> = operands
             .split(' ')
             .map(str::parse)
             .collect::<Result<_, _>>()?;
 
+        let operand_digits = operands.iter().map(|n| count_digits(*n)).collect();
+
+        let (result, operators) = match result.split_once(' ') {
+            Some((result, operators)) => {
+                let operators = operators
+                    .strip_prefix('[')
+                    .and_then(|operators| operators.strip_suffix(']'))
+                    .ok_or_else(|| anyhow!("Operator set '{operators}' not in brackets"))?
+                    .chars()
+                    .map(|c| match c {
+                        '+' => Ok(Operator::Add),
+                        '*' => Ok(Operator::Multiply),
+                        '|' => Ok(Operator::Concat),
+                        _ => Err(anyhow!("Unknown operator '{c}'")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                ensure!(!operators.is_empty(), "Empty operator set");
+
+                (result, operators)
+            }
+            None => (result, vec![Operator::Add, Operator::Multiply]),
+        };
+
         let result = result.parse()?;
 
-        Ok(OplessEquation { operands, result })
+        Ok(OplessEquation {
+            operands,
+            operand_digits,
+            result,
+            operators,
+        })
     }
 }
 
@@ -41,6 +94,10 @@ impl FromStr for Problem {
     }
 }
 
+fn count_digits(n: usize) -> u32 {
+    n.checked_ilog10().unwrap_or(0) + 1
+}
+
 fn try_operation(val: usize, remaining: &[usize], expected: usize) -> bool {
     if remaining.is_empty() {
         return val == expected;
@@ -50,30 +107,129 @@ fn try_operation(val: usize, remaining: &[usize], expected: usize) -> bool {
         || try_operation(val * remaining[0], &remaining[1..], expected)
 }
 
+// answers (for `+` and `*` only) without the full search when there is a
+// single operand, or when all the remaining ones are 1 - multiplying by 1
+// keeps the value, adding 1 bumps it, so anything from the first operand up
+// to the first operand plus the number of ones is reachable
+fn trivially_solvable(operands: &[usize], result: usize) -> Option<bool> {
+    let (first, rest) = operands.split_first()?;
+
+    if rest.iter().all(|n| *n == 1) {
+        return Some((*first..=first + rest.len()).contains(&result));
+    }
+
+    None
+}
+
 #[must_use]
 pub fn solve_part_1(p: &Problem) -> usize {
     let Problem { equations } = p;
 
     equations
         .iter()
-        .filter_map(|OplessEquation { operands, result }| {
-            try_operation(operands[0], &operands[1..], *result).then_some(result)
+        .filter_map(|equation| {
+            let OplessEquation {
+                operands, result, ..
+            } = equation;
+
+            trivially_solvable(operands, *result)
+                .unwrap_or_else(|| try_operation(operands[0], &operands[1..], *result))
+                .then_some(result)
+        })
+        .sum()
+}
+
+// `(a + b) % modulus` and `(a * b) % modulus`, computed in `u128` so that
+// neither overflows, whatever the modulus
+#[allow(clippy::cast_possible_truncation)]
+fn add_mul_mod(a: usize, b: usize, modulus: usize) -> (usize, usize) {
+    let (a, b, modulus) = (a as u128, b as u128, modulus as u128);
+
+    // both below `modulus`, so fit back into `usize`
+    (((a + b) % modulus) as usize, ((a * b) % modulus) as usize)
+}
+
+// like `try_operation`, but compares (and accumulates) values modulo `modulus`
+fn try_operation_mod(val: usize, remaining: &[usize], expected: usize, modulus: usize) -> bool {
+    if remaining.is_empty() {
+        return val == expected;
+    }
+
+    let (sum, product) = add_mul_mod(val, remaining[0] % modulus, modulus);
+    let rest = &remaining[1..];
+
+    try_operation_mod(sum, rest, expected, modulus)
+        || try_operation_mod(product, rest, expected, modulus)
+}
+
+// `modulus` of 0 means exact comparison, same as `solve_part_1`; it's a `usize`
+// (rather than `u64`) like all the other values in this crate
+#[must_use]
+pub fn solve_part_1_mod(p: &Problem, modulus: usize) -> usize {
+    if modulus == 0 {
+        return solve_part_1(p);
+    }
+
+    let Problem { equations } = p;
+
+    equations
+        .iter()
+        .filter_map(|equation| {
+            let OplessEquation {
+                operands, result, ..
+            } = equation;
+
+            try_operation_mod(
+                operands[0] % modulus,
+                &operands[1..],
+                result % modulus,
+                modulus,
+            )
+            .then_some(result)
         })
         .sum()
 }
 
-fn concat(a: usize, b: usize) -> usize {
-    a * 10usize.pow(b.ilog10() + 1) + b
+fn concat(a: usize, b: usize, b_digits: u32) -> usize {
+    a * 10usize.pow(b_digits) + b
+}
+
+// `remaining_digits` holds precomputed digit counts of `remaining` operands
+fn try_operation_with_concat(
+    val: usize,
+    remaining: &[usize],
+    remaining_digits: &[u32],
+    expected: usize,
+) -> bool {
+    try_operation_with_concat_counted(val, remaining, remaining_digits, expected, &mut 0)
 }
 
-fn try_operation_with_concat(val: usize, remaining: &[usize], expected: usize) -> bool {
+// `calls` is incremented on every (recursive) invocation
+fn try_operation_with_concat_counted(
+    val: usize,
+    remaining: &[usize],
+    remaining_digits: &[u32],
+    expected: usize,
+    calls: &mut usize,
+) -> bool {
+    *calls += 1;
+
     if remaining.is_empty() {
         return val == expected;
     }
 
-    try_operation_with_concat(val + remaining[0], &remaining[1..], expected)
-        || try_operation_with_concat(val * remaining[0], &remaining[1..], expected)
-        || try_operation_with_concat(concat(val, remaining[0]), &remaining[1..], expected)
+    let rest = &remaining[1..];
+    let rest_digits = &remaining_digits[1..];
+
+    try_operation_with_concat_counted(val + remaining[0], rest, rest_digits, expected, calls)
+        || try_operation_with_concat_counted(val * remaining[0], rest, rest_digits, expected, calls)
+        || try_operation_with_concat_counted(
+            concat(val, remaining[0], remaining_digits[0]),
+            rest,
+            rest_digits,
+            expected,
+            calls,
+        )
 }
 
 #[must_use]
@@ -82,12 +238,207 @@ pub fn solve_part_2(p: &Problem) -> usize {
 
     equations
         .iter()
-        .filter_map(|OplessEquation { operands, result }| {
-            try_operation_with_concat(operands[0], &operands[1..], *result).then_some(result)
+        .filter_map(|equation| {
+            let OplessEquation {
+                operands,
+                operand_digits,
+                result,
+                ..
+            } = equation;
+
+            try_operation_with_concat(operands[0], &operands[1..], &operand_digits[1..], *result)
+                .then_some(result)
         })
         .sum()
 }
 
+// like `try_operation_with_concat`, but trying only the given `operators`
+fn try_operators(
+    val: usize,
+    remaining: &[usize],
+    remaining_digits: &[u32],
+    expected: usize,
+    operators: &[Operator],
+) -> bool {
+    if remaining.is_empty() {
+        return val == expected;
+    }
+
+    operators.iter().any(|operator| {
+        try_operators(
+            operator.apply(val, remaining[0], remaining_digits[0]),
+            &remaining[1..],
+            &remaining_digits[1..],
+            expected,
+            operators,
+        )
+    })
+}
+
+// like `solve_part_1`, but each equation is solved with its own operator set
+#[must_use]
+pub fn solve_part_1_per_line_ops(p: &Problem) -> usize {
+    let Problem { equations } = p;
+
+    equations
+        .iter()
+        .filter_map(|equation| {
+            let OplessEquation {
+                operands,
+                operand_digits,
+                result,
+                operators,
+            } = equation;
+
+            try_operators(
+                operands[0],
+                &operands[1..],
+                &operand_digits[1..],
+                *result,
+                operators,
+            )
+            .then_some(result)
+        })
+        .sum()
+}
+
+// returns part 2 answer along with the total number of recursive calls made
+// while searching for operators
+#[must_use]
+pub fn solve_part_2_with_stats(p: &Problem) -> (usize, usize) {
+    let Problem { equations } = p;
+
+    let mut calls = 0;
+
+    let sum = equations
+        .iter()
+        .filter_map(|equation| {
+            let OplessEquation {
+                operands,
+                operand_digits,
+                result,
+                ..
+            } = equation;
+
+            try_operation_with_concat_counted(
+                operands[0],
+                &operands[1..],
+                &operand_digits[1..],
+                *result,
+                &mut calls,
+            )
+            .then_some(result)
+        })
+        .sum();
+
+    (sum, calls)
+}
+
+// whether each equation (in input order) can be made true, with or without
+// the concatenation operator; equations are checked in parallel
+#[cfg(feature = "rayon")]
+#[must_use]
+pub fn per_equation_results(p: &Problem, allow_concat: bool) -> Vec<bool> {
+    use rayon::prelude::*;
+
+    let Problem { equations } = p;
+
+    equations
+        .par_iter()
+        .map(|equation| {
+            let OplessEquation {
+                operands,
+                operand_digits,
+                result,
+                ..
+            } = equation;
+
+            if allow_concat {
+                try_operation_with_concat(
+                    operands[0],
+                    &operands[1..],
+                    &operand_digits[1..],
+                    *result,
+                )
+            } else {
+                try_operation(operands[0], &operands[1..], *result)
+            }
+        })
+        .collect()
+}
+
+// appends the node for `val` and its whole subtree to `out`, returning the id
+// of that node and whether any leaf below it evaluates to `expected`
+fn write_decision_subtree(
+    val: usize,
+    remaining: &[usize],
+    expected: usize,
+    allow_concat: bool,
+    next_id: &mut usize,
+    out: &mut String,
+) -> (usize, bool) {
+    let id = *next_id;
+    *next_id += 1;
+
+    let reaches_expected = if let Some((&operand, rest)) = remaining.split_first() {
+        let mut branches = vec![("+", val + operand), ("*", val * operand)];
+
+        if allow_concat {
+            branches.push(("||", concat(val, operand, count_digits(operand))));
+        }
+
+        let mut reaches_expected = false;
+
+        for (operator, child_val) in branches {
+            let (child_id, child_reaches_expected) =
+                write_decision_subtree(child_val, rest, expected, allow_concat, next_id, out);
+
+            let color = if child_reaches_expected {
+                ", color=green"
+            } else {
+                ""
+            };
+
+            writeln!(
+                out,
+                "    n{id} -> n{child_id} [label=\"{operator}\"{color}];"
+            )
+            .unwrap();
+
+            reaches_expected |= child_reaches_expected;
+        }
+
+        reaches_expected
+    } else {
+        val == expected
+    };
+
+    let color = if reaches_expected {
+        ", color=green"
+    } else {
+        ""
+    };
+
+    writeln!(out, "    n{id} [label=\"{val}\"{color}];").unwrap();
+
+    (id, reaches_expected)
+}
+
+// Graphviz DOT rendering of every operator combination tried for the
+// equation, with the paths evaluating to `result` highlighted in green
+#[must_use]
+pub fn decision_tree_dot(operands: &[usize], result: usize, allow_concat: bool) -> String {
+    let mut out = String::from("digraph {\n");
+
+    if let Some((&first, rest)) = operands.split_first() {
+        write_decision_subtree(first, rest, result, allow_concat, &mut 0, &mut out);
+    }
+
+    out.push_str("}\n");
+
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,7 +463,9 @@ mod tests {
             p.equations[0],
             OplessEquation {
                 operands: vec![10, 19],
+                operand_digits: vec![2, 2],
                 result: 190,
+                operators: vec![Operator::Add, Operator::Multiply],
             }
         );
 
@@ -120,19 +473,85 @@ mod tests {
             p.equations[8],
             OplessEquation {
                 operands: vec![11, 6, 16, 20],
+                operand_digits: vec![2, 1, 2, 2],
                 result: 292,
+                operators: vec![Operator::Add, Operator::Multiply],
             }
         );
     }
 
+    #[test]
+    fn test_solve_part_1_per_line_ops() {
+        let p: Problem = "\
+190 [+*]: 10 19
+156 [+*|]: 15 6
+156: 15 6
+83: 17 5
+7290 [*]: 6 8 6 15
+1440 [*]: 6 8 2 15
+192 [|]: 17 8 14"
+            .parse()
+            .unwrap();
+
+        assert_eq!(p.equations[1].operators.len(), 3);
+        assert_eq!(p.equations[4].operators, vec![Operator::Multiply]);
+        assert_eq!(solve_part_1_per_line_ops(&p), 190 + 156 + 1440);
+
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(solve_part_1_per_line_ops(&p), solve_part_1(&p));
+
+        assert!("190 [+-]: 10 19".parse::<Problem>().is_err());
+        assert!("190 []: 10 19".parse::<Problem>().is_err());
+        assert!("190 +*: 10 19".parse::<Problem>().is_err());
+    }
+
+    #[test]
+    fn test_single_operand_equation() {
+        let p: Problem = "5: 5".parse().unwrap();
+
+        assert_eq!(
+            p.equations[0],
+            OplessEquation {
+                operands: vec![5],
+                operand_digits: vec![1],
+                result: 5,
+                operators: vec![Operator::Add, Operator::Multiply],
+            }
+        );
+        assert_eq!(solve_part_1(&p), 5);
+        assert_eq!(solve_part_2(&p), 5);
+
+        let p: Problem = "6: 5".parse().unwrap();
+
+        assert_eq!(solve_part_1(&p), 0);
+        assert_eq!(solve_part_2(&p), 0);
+    }
+
+    #[test]
+    fn test_trivially_solvable() {
+        assert_eq!(trivially_solvable(&[5], 5), Some(true));
+        assert_eq!(trivially_solvable(&[5], 6), Some(false));
+        assert_eq!(trivially_solvable(&[5, 1, 1], 5), Some(true));
+        assert_eq!(trivially_solvable(&[5, 1, 1], 7), Some(true));
+        assert_eq!(trivially_solvable(&[5, 1, 1], 4), Some(false));
+        assert_eq!(trivially_solvable(&[5, 1, 1], 8), Some(false));
+        assert_eq!(trivially_solvable(&[10, 19], 190), None);
+        assert_eq!(trivially_solvable(&[], 0), None);
+
+        let p: Problem = "5: 5 1 1\n7: 5 1 1\n8: 5 1 1".parse().unwrap();
+
+        assert_eq!(solve_part_1(&p), 5 + 7);
+    }
+
     #[test]
     fn test_try_operation() {
-        assert_eq!(try_operation(10, &[19], 190), true);
-        assert_eq!(try_operation(81, &[40, 27], 3267), true);
-        assert_eq!(try_operation(11, &[6, 16, 20], 292), true);
-        assert_eq!(try_operation(17, &[5], 83), false);
-        assert_eq!(try_operation(16, &[10, 13], 161011), false);
-        assert_eq!(try_operation(9, &[7, 18, 13], 21037), false);
+        assert!(try_operation(10, &[19], 190));
+        assert!(try_operation(81, &[40, 27], 3267));
+        assert!(try_operation(11, &[6, 16, 20], 292));
+        assert!(!try_operation(17, &[5], 83));
+        assert!(!try_operation(16, &[10, 13], 161_011));
+        assert!(!try_operation(9, &[7, 18, 13], 21037));
     }
 
     #[test]
@@ -142,6 +561,33 @@ mod tests {
         assert_eq!(solve_part_1(&p), 3749);
     }
 
+    #[test]
+    fn test_solve_part_1_mod() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(solve_part_1_mod(&p, 0), 3749);
+
+        // neither 17 + 5 = 22 nor 17 * 5 = 85 equals 83, but 85 = 83 (mod 2)
+        let p: Problem = "83: 17 5".parse().unwrap();
+
+        assert_eq!(solve_part_1(&p), 0);
+        assert_eq!(solve_part_1_mod(&p, 0), 0);
+        assert_eq!(solve_part_1_mod(&p, 2), 83);
+        assert_eq!(solve_part_1_mod(&p, 4), 0);
+
+        // (2^40 - 1)^2 = 1 (mod 2^40), but overflows a `u64` when multiplied
+        let p: Problem = "1: 1099511627775 1099511627775".parse().unwrap();
+
+        assert_eq!(solve_part_1_mod(&p, 1 << 40), 1);
+
+        // (2^64 - 2)^2 = 1 (mod 2^64 - 1), and even their sum overflows
+        let p: Problem = "1: 18446744073709551614 18446744073709551614"
+            .parse()
+            .unwrap();
+
+        assert_eq!(solve_part_1_mod(&p, usize::MAX), 1);
+    }
+
     #[test]
     fn test_digits_in_number() {
         assert_eq!(101usize.ilog10() + 1, 3);
@@ -152,19 +598,43 @@ mod tests {
 
     #[test]
     fn test_concat() {
-        assert_eq!(concat(123, 456), 123456);
-        assert_eq!(concat(9876, 789), 9876789);
-        assert_eq!(concat(48, 6), 486);
+        assert_eq!(concat(123, 456, 3), 123_456);
+        assert_eq!(concat(9876, 789, 3), 9_876_789);
+        assert_eq!(concat(48, 6, 1), 486);
+    }
+
+    #[test]
+    fn test_operand_digits() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        for equation in &p.equations {
+            assert_eq!(
+                equation.operand_digits,
+                equation
+                    .operands
+                    .iter()
+                    .map(|n| n.ilog10() + 1)
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        assert_eq!(count_digits(0), 1);
+        assert_eq!(solve_part_2(&p), 11387);
     }
 
     #[test]
     fn test_try_operation_with_concat() {
-        assert_eq!(try_operation_with_concat(15, &[6], 156), true);
-        assert_eq!(try_operation_with_concat(6, &[8, 6, 15], 7290), true);
-        assert_eq!(try_operation_with_concat(17, &[8, 14], 192), true);
-        assert_eq!(try_operation_with_concat(17, &[5], 83), false);
-        assert_eq!(try_operation_with_concat(16, &[10, 13], 161011), false);
-        assert_eq!(try_operation_with_concat(9, &[7, 18, 13], 21037), false);
+        assert!(try_operation_with_concat(15, &[6], &[1], 156));
+        assert!(try_operation_with_concat(6, &[8, 6, 15], &[1, 1, 2], 7290));
+        assert!(try_operation_with_concat(17, &[8, 14], &[1, 2], 192));
+        assert!(!try_operation_with_concat(17, &[5], &[1], 83));
+        assert!(!try_operation_with_concat(16, &[10, 13], &[2, 2], 161_011));
+        assert!(!try_operation_with_concat(
+            9,
+            &[7, 18, 13],
+            &[1, 2, 2],
+            21037
+        ));
     }
 
     #[test]
@@ -173,4 +643,61 @@ mod tests {
 
         assert_eq!(solve_part_2(&p), 11387);
     }
+
+    #[test]
+    fn test_solve_part_2_with_stats() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let (sum, calls) = solve_part_2_with_stats(&p);
+
+        assert_eq!(sum, 11387);
+        assert!(calls > 0);
+
+        let p: Problem = "156: 15 6".parse().unwrap();
+
+        // 15, then 15 + 6, 15 * 6 and finally 15 || 6
+        assert_eq!(solve_part_2_with_stats(&p), (156, 4));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_per_equation_results() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let results = per_equation_results(&p, false);
+
+        assert_eq!(
+            results,
+            vec![true, true, false, false, false, false, false, false, true]
+        );
+        assert_eq!(results.iter().filter(|solvable| **solvable).count(), 3);
+
+        let results = per_equation_results(&p, true);
+
+        assert_eq!(
+            results,
+            vec![true, true, false, true, true, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_decision_tree_dot() {
+        let dot = decision_tree_dot(&[10, 19], 190, false);
+
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("n0 [label=\"10\", color=green];"));
+        assert!(dot.contains("[label=\"29\"];"));
+        assert!(dot.contains("[label=\"190\", color=green];"));
+        assert!(dot.contains("n0 -> n2 [label=\"*\", color=green];"));
+        assert!(!dot.contains("||"));
+
+        let dot = decision_tree_dot(&[15, 6], 156, true);
+
+        assert!(dot.contains("[label=\"||\", color=green];"));
+        assert!(dot.contains("[label=\"156\", color=green];"));
+
+        let dot = decision_tree_dot(&[17, 5], 83, true);
+
+        assert!(!dot.contains("color=green"));
+    }
 }
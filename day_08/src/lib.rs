@@ -38,6 +38,28 @@ impl FromStr for Problem {
     }
 }
 
+// `(min, max)` corners of a bounding box
+pub type Bounds = ((usize, usize), (usize, usize));
+
+#[must_use]
+pub fn frequency_bounds(p: &Problem) -> HashMap<char, Bounds> {
+    let Problem { antennas, .. } = p;
+
+    antennas
+        .iter()
+        .map(|(frequency, antennas)| {
+            let bounds = antennas.iter().fold(
+                ((usize::MAX, usize::MAX), (0, 0)),
+                |((min_x, min_y), (max_x, max_y)), &(x, y)| {
+                    ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+                },
+            );
+
+            (*frequency, bounds)
+        })
+        .collect()
+}
+
 fn get_antinodes(
     a: (usize, usize),
     b: (usize, usize),
@@ -72,11 +94,14 @@ fn get_antinodes(
     result
 }
 
-fn get_antinodes_multi(
+// the antennas themselves along with grid positions in line with them, spaced
+// by their distance - up to `max_steps` of them beyond each of the antennas
+fn get_antinodes_harmonic(
     a: (usize, usize),
     b: (usize, usize),
     map_height: usize,
     map_width: usize,
+    max_steps: usize,
 ) -> Vec<(usize, usize)> {
     let mut result = vec![a, b];
 
@@ -88,35 +113,44 @@ fn get_antinodes_multi(
     #[allow(clippy::cast_possible_wrap)]
     let ba_diff = (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
 
+    let mut steps = 0;
+
     while let (Some(anti_a_x), Some(anti_a_y)) = (
         a.0.checked_add_signed(ab_diff.0),
         a.1.checked_add_signed(ab_diff.1),
     ) {
-        if anti_a_x >= map_height || anti_a_y >= map_width {
+        if steps == max_steps || anti_a_x >= map_height || anti_a_y >= map_width {
             break;
         }
 
         result.push((anti_a_x, anti_a_y));
         a = (anti_a_x, anti_a_y);
+        steps += 1;
     }
 
+    let mut steps = 0;
+
     while let (Some(anti_b_x), Some(anti_b_y)) = (
         b.0.checked_add_signed(ba_diff.0),
         b.1.checked_add_signed(ba_diff.1),
     ) {
-        if anti_b_x >= map_height || anti_b_y >= map_width {
+        if steps == max_steps || anti_b_x >= map_height || anti_b_y >= map_width {
             break;
         }
 
         result.push((anti_b_x, anti_b_y));
         b = (anti_b_x, anti_b_y);
+        steps += 1;
     }
 
     result
 }
 
+// antinodes at `a + k * (b - a)` for every ordered pair of same-frequency
+// antennas `(a, b)` and every `k` in `multiples` - `[-1]` yields the part 1
+// antinodes, while `0, 1, 2, ...` (until out of the map) yields part 2 ones
 #[must_use]
-pub fn solve_part_1(p: &Problem) -> usize {
+pub fn antinodes_at_multiples(p: &Problem, multiples: &[isize]) -> HashSet<(usize, usize)> {
     let Problem {
         antennas,
         map_height,
@@ -129,22 +163,33 @@ pub fn solve_part_1(p: &Problem) -> usize {
         let antinodes = antennas
             .iter()
             .cartesian_product(antennas)
+            .filter(|(a, b)| a != b)
             .flat_map(|(a, b)| {
-                if a == b {
-                    return vec![];
-                }
+                #[allow(clippy::cast_possible_wrap)]
+                let ab_diff = (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
+
+                multiples.iter().filter_map(move |k| {
+                    let x = a.0.checked_add_signed(k.checked_mul(ab_diff.0)?)?;
+                    let y = a.1.checked_add_signed(k.checked_mul(ab_diff.1)?)?;
 
-                get_antinodes(*a, *b, *map_height, *map_width)
+                    (x < *map_height && y < *map_width).then_some((x, y))
+                })
             });
 
         unique_antinode_locations.extend(antinodes);
     }
 
-    unique_antinode_locations.len()
+    unique_antinode_locations
 }
 
+// part 1 antinodes, but only from pairs of antennas `(a, b)` whose vector
+// `b - a`, as `(dx, dy)`, satisfies `predicate` (e.g. `dx == 0` for pairs
+// lying in the same row, `dx.abs() == dy.abs()` for diagonal ones)
 #[must_use]
-pub fn solve_part_2(p: &Problem) -> usize {
+pub fn antinodes_filtered(
+    p: &Problem,
+    predicate: impl Fn((isize, isize)) -> bool,
+) -> HashSet<(usize, usize)> {
     let Problem {
         antennas,
         map_height,
@@ -157,18 +202,208 @@ pub fn solve_part_2(p: &Problem) -> usize {
         let antinodes = antennas
             .iter()
             .cartesian_product(antennas)
-            .flat_map(|(a, b)| {
-                if a == b {
-                    return vec![];
-                }
+            .filter(|(a, b)| {
+                #[allow(clippy::cast_possible_wrap)]
+                let ab_diff = (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
 
-                get_antinodes_multi(*a, *b, *map_height, *map_width)
-            });
+                a != b && predicate(ab_diff)
+            })
+            .flat_map(|(a, b)| get_antinodes(*a, *b, *map_height, *map_width));
+
+        unique_antinode_locations.extend(antinodes);
+    }
+
+    unique_antinode_locations
+}
+
+fn manhattan_distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+// part 1 antinodes, but only those within Manhattan distance `d` of either
+// antenna of the pair that produced them
+#[must_use]
+pub fn antinodes_within_distance(p: &Problem, d: usize) -> HashSet<(usize, usize)> {
+    let Problem {
+        antennas,
+        map_height,
+        map_width,
+    } = p;
+
+    let mut unique_antinode_locations: HashSet<(usize, usize)> = HashSet::new();
+
+    for antennas in antennas.values() {
+        let antinodes = antennas.iter().tuple_combinations().flat_map(|(a, b)| {
+            get_antinodes(*a, *b, *map_height, *map_width)
+                .into_iter()
+                .filter(move |antinode| {
+                    manhattan_distance(*antinode, *a).min(manhattan_distance(*antinode, *b)) <= d
+                })
+        });
 
         unique_antinode_locations.extend(antinodes);
     }
 
-    unique_antinode_locations.len()
+    unique_antinode_locations
+}
+
+// keeps part 1 antinodes up to date as antennas are placed one at a time -
+// each new antenna only pairs with the already placed ones of its frequency
+#[derive(Debug, Default)]
+pub struct AntinodeTracker {
+    antennas: HashMap<char, Vec<(usize, usize)>>,
+    antinodes: HashSet<(usize, usize)>,
+    map_height: usize,
+    map_width: usize,
+}
+
+impl AntinodeTracker {
+    #[must_use]
+    pub fn new(map_height: usize, map_width: usize) -> Self {
+        AntinodeTracker {
+            map_height,
+            map_width,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_antenna(&mut self, frequency: char, position: (usize, usize)) {
+        let same_frequency = self.antennas.entry(frequency).or_default();
+
+        for &other in same_frequency.iter() {
+            if other == position {
+                continue;
+            }
+
+            self.antinodes.extend(get_antinodes(
+                other,
+                position,
+                self.map_height,
+                self.map_width,
+            ));
+        }
+
+        same_frequency.push(position);
+    }
+
+    #[must_use]
+    pub fn antinodes(&self) -> &HashSet<(usize, usize)> {
+        &self.antinodes
+    }
+
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.antinodes.len()
+    }
+}
+
+// every (part 1) antinode within the map
+#[must_use]
+pub fn antinode_locations(p: &Problem) -> HashSet<(usize, usize)> {
+    let Problem {
+        antennas,
+        map_height,
+        map_width,
+    } = p;
+
+    let mut tracker = AntinodeTracker::new(*map_height, *map_width);
+
+    for (frequency, antennas) in antennas {
+        for antenna in antennas {
+            tracker.add_antenna(*frequency, *antenna);
+        }
+    }
+
+    tracker.antinodes().clone()
+}
+
+#[must_use]
+pub fn solve_part_1(p: &Problem) -> usize {
+    antinode_locations(p).len()
+}
+
+// part 1 antinodes together with their point reflections across the grid
+// center `(map_height / 2, map_width / 2)`; reflections falling outside of the
+// map (possible for even map sizes) are dropped
+#[must_use]
+pub fn solve_mirrored(p: &Problem) -> usize {
+    let Problem {
+        map_height,
+        map_width,
+        ..
+    } = p;
+
+    let center = (map_height / 2, map_width / 2);
+
+    let antinodes = antinodes_at_multiples(p, &[-1]);
+
+    let reflections = antinodes.iter().filter_map(|&(x, y)| {
+        let reflected_x = (2 * center.0).checked_sub(x)?;
+        let reflected_y = (2 * center.1).checked_sub(y)?;
+
+        (reflected_x < *map_height && reflected_y < *map_width)
+            .then_some((reflected_x, reflected_y))
+    });
+
+    antinodes
+        .iter()
+        .copied()
+        .chain(reflections)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+// part 1 (or, with `harmonic`, part 2) antinodes sorted by `(x, y)`
+#[must_use]
+pub fn antinode_locations_sorted(p: &Problem, harmonic: bool) -> Vec<(usize, usize)> {
+    let antinodes = if harmonic {
+        harmonic_antinode_locations(p)
+    } else {
+        antinode_locations(p)
+    };
+
+    let mut antinodes: Vec<_> = antinodes.into_iter().collect();
+
+    antinodes.sort_unstable();
+
+    antinodes
+}
+
+#[must_use]
+pub fn solve_part_2(p: &Problem) -> usize {
+    harmonic_antinode_locations(p).len()
+}
+
+// part 2, but with at most `max_steps` antinodes placed beyond each antenna of
+// a pair - 1 gives part 1 antinodes along with the antennas themselves
+#[must_use]
+pub fn solve_harmonic_limited(p: &Problem, max_steps: usize) -> usize {
+    harmonic_antinode_locations_limited(p, max_steps).len()
+}
+
+fn harmonic_antinode_locations(p: &Problem) -> HashSet<(usize, usize)> {
+    harmonic_antinode_locations_limited(p, usize::MAX)
+}
+
+fn harmonic_antinode_locations_limited(p: &Problem, max_steps: usize) -> HashSet<(usize, usize)> {
+    let Problem {
+        antennas,
+        map_height,
+        map_width,
+    } = p;
+
+    let mut unique_antinode_locations: HashSet<(usize, usize)> = HashSet::new();
+
+    for antennas in antennas.values() {
+        let antinodes = antennas
+            .iter()
+            .tuple_combinations()
+            .flat_map(|(a, b)| get_antinodes_harmonic(*a, *b, *map_height, *map_width, max_steps));
+
+        unique_antinode_locations.extend(antinodes);
+    }
+
+    unique_antinode_locations
 }
 
 #[cfg(test)]
@@ -201,6 +436,17 @@ mod tests {
         assert_eq!(p.map_width, 12);
     }
 
+    #[test]
+    fn test_frequency_bounds() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let bounds = frequency_bounds(&p);
+
+        assert_eq!(bounds.len(), 2);
+        assert_eq!(bounds[&'A'], ((5, 6), (9, 9)));
+        assert_eq!(bounds[&'0'], ((1, 4), (4, 8)));
+    }
+
     #[test]
     fn test_get_antinodes() {
         assert_eq!(get_antinodes((3, 4), (5, 5), 12, 12), vec![(1, 3), (7, 6)]);
@@ -209,6 +455,22 @@ mod tests {
         assert_eq!(get_antinodes((5, 4), (3, 5), 12, 12), vec![(7, 3), (1, 6)]);
     }
 
+    #[test]
+    fn test_get_antinodes_harmonic() {
+        assert_eq!(
+            get_antinodes_harmonic((3, 4), (5, 5), 12, 12, usize::MAX),
+            vec![(3, 4), (5, 5), (1, 3), (7, 6), (9, 7), (11, 8)]
+        );
+        assert_eq!(
+            get_antinodes_harmonic((0, 0), (1, 2), 3, 3, usize::MAX),
+            vec![(0, 0), (1, 2)]
+        );
+        assert_eq!(
+            get_antinodes_harmonic((3, 4), (5, 5), 12, 12, 1),
+            vec![(3, 4), (5, 5), (1, 3), (7, 6)]
+        );
+    }
+
     #[test]
     fn test_solve_part_1() {
         let p: Problem = TEST_INPUT.parse().unwrap();
@@ -216,10 +478,182 @@ mod tests {
         assert_eq!(solve_part_1(&p), 14);
     }
 
+    #[test]
+    fn test_antinode_locations() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let antinodes = antinode_locations(&p);
+
+        assert_eq!(antinodes.len(), 14);
+        assert!(antinodes.contains(&(0, 6)));
+        assert!(antinodes.contains(&(0, 11)));
+        assert!(!antinodes.contains(&(0, 0)));
+        assert_eq!(antinodes, antinodes_at_multiples(&p, &[-1]));
+    }
+
+    #[test]
+    fn test_antinode_tracker() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let mut tracker = AntinodeTracker::new(p.map_height, p.map_width);
+
+        tracker.add_antenna('A', (5, 6));
+        assert_eq!(tracker.count(), 0);
+        tracker.add_antenna('0', (1, 8));
+        assert_eq!(tracker.count(), 0);
+        tracker.add_antenna('A', (8, 8));
+        assert_eq!(tracker.antinodes(), &HashSet::from([(2, 4), (11, 10)]));
+
+        let mut tracker = AntinodeTracker::new(p.map_height, p.map_width);
+
+        for (frequency, antennas) in &p.antennas {
+            for antenna in antennas {
+                tracker.add_antenna(*frequency, *antenna);
+            }
+        }
+
+        assert_eq!(tracker.count(), 14);
+    }
+
+    #[test]
+    fn test_antinodes_at_multiples() {
+        let p: Problem = "\
+............
+............
+............
+....a.......
+............
+.....a......
+............
+............
+............
+............
+............
+............"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            antinodes_at_multiples(&p, &[1, 2]),
+            HashSet::from([(3, 4), (5, 5), (1, 3), (7, 6)])
+        );
+        assert_eq!(
+            antinodes_at_multiples(&p, &[-1]),
+            HashSet::from([(1, 3), (7, 6)])
+        );
+        // far off the map, however the multiplication turns out
+        assert_eq!(
+            antinodes_at_multiples(&p, &[isize::MIN, isize::MAX, 1]),
+            HashSet::from([(5, 5), (3, 4)])
+        );
+
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(antinodes_at_multiples(&p, &[-1]).len(), 14);
+    }
+
+    #[test]
+    fn test_antinodes_filtered() {
+        let p: Problem = "\
+......
+.a.a..
+......
+.b....
+..b...
+......"
+            .parse()
+            .unwrap();
+
+        // only the 'a' antennas share a row
+        assert_eq!(
+            antinodes_filtered(&p, |(dx, _)| dx == 0),
+            HashSet::from([(1, 5)])
+        );
+        assert_eq!(
+            antinodes_filtered(&p, |(dx, dy)| dx.abs() == dy.abs()),
+            HashSet::from([(2, 0), (5, 3)])
+        );
+        assert!(antinodes_filtered(&p, |(_, dy)| dy == 0).is_empty());
+        assert_eq!(
+            antinodes_filtered(&p, |_| true),
+            antinodes_at_multiples(&p, &[-1])
+        );
+    }
+
+    #[test]
+    fn test_antinodes_within_distance() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert!(antinodes_within_distance(&p, 1).is_empty());
+        // only the closest 'A' antennas, (8, 8) and (9, 9), are in range
+        assert_eq!(
+            antinodes_within_distance(&p, 2),
+            HashSet::from([(7, 7), (10, 10)])
+        );
+        // (7, 0) comes from '0' antennas (1, 8) and (4, 4), 7 apart
+        assert!(!antinodes_within_distance(&p, 6).contains(&(7, 0)));
+        assert!(antinodes_within_distance(&p, 7).contains(&(7, 0)));
+        assert_eq!(antinodes_within_distance(&p, 24).len(), 14);
+    }
+
+    #[test]
+    fn test_solve_mirrored() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert!(solve_mirrored(&p) >= 14);
+
+        // antinode at (3, 3) reflects across (2, 2) onto (1, 1), while the
+        // one at (0, 0) would be reflected outside of the map
+        let p: Problem = "\
+....
+.a..
+..a.
+...."
+            .parse()
+            .unwrap();
+
+        assert_eq!(solve_part_1(&p), 2);
+        assert_eq!(solve_mirrored(&p), 3);
+    }
+
+    #[test]
+    fn test_antinode_locations_sorted() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        let antinodes = antinode_locations_sorted(&p, false);
+
+        assert_eq!(antinodes.len(), 14);
+        assert!(antinodes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(antinodes[0], (0, 6));
+
+        let antinodes = antinode_locations_sorted(&p, true);
+
+        assert_eq!(antinodes.len(), 34);
+        assert!(antinodes.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
     #[test]
     fn test_solve_part_2() {
         let p: Problem = TEST_INPUT.parse().unwrap();
 
         assert_eq!(solve_part_2(&p), 34);
     }
+
+    #[test]
+    fn test_solve_harmonic_limited() {
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(
+            solve_harmonic_limited(&p, 2),
+            antinodes_at_multiples(&p, &[-2, -1, 0, 1]).len()
+        );
+        assert!(solve_harmonic_limited(&p, 2) < solve_part_2(&p));
+        assert_eq!(
+            solve_harmonic_limited(&p, 1),
+            antinodes_at_multiples(&p, &[-1, 0, 1]).len()
+        );
+        // only the antennas themselves
+        assert_eq!(solve_harmonic_limited(&p, 0), 7);
+        assert_eq!(solve_harmonic_limited(&p, 100), 34);
+    }
 }
@@ -1,9 +1,9 @@
-use anyhow::anyhow;
-use std::iter::repeat;
+use anyhow::{anyhow, ensure};
+use std::iter::repeat_n;
 use std::str::FromStr;
 
 #[derive(Debug, Eq, PartialEq)]
-enum DiskMapEntry {
+pub enum DiskMapEntry {
     File { id: usize, length: usize },
     FreeSpace(usize),
 }
@@ -39,6 +39,213 @@ impl FromStr for Problem {
     }
 }
 
+impl Problem {
+    #[must_use]
+    pub fn disk_map(&self) -> &[DiskMapEntry] {
+        &self.disk_map
+    }
+
+    /// Checks that the disk map describes something to compact.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the disk map is empty or has no file occupying at
+    /// least one block.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let Problem { disk_map } = self;
+
+        ensure!(!disk_map.is_empty(), "Empty disk map");
+        ensure!(
+            disk_map
+                .iter()
+                .any(|entry| matches!(entry, DiskMapEntry::File { length, .. } if *length > 0)),
+            "Disk map has no non-empty files"
+        );
+
+        Ok(())
+    }
+
+    // `(start_position, length)` of every free space entry (including empty
+    // ones) in the uncompacted disk layout
+    #[must_use]
+    pub fn free_runs(&self) -> Vec<(usize, usize)> {
+        free_space_runs(&self.disk_map).collect()
+    }
+}
+
+// `(start_position, entry)` of every entry in the uncompacted disk layout
+fn positioned_entries(disk_map: &[DiskMapEntry]) -> impl Iterator<Item = (usize, &DiskMapEntry)> {
+    disk_map.iter().scan(0, |position, entry| {
+        let start = *position;
+
+        *position += match *entry {
+            DiskMapEntry::File { length, .. } | DiskMapEntry::FreeSpace(length) => length,
+        };
+
+        Some((start, entry))
+    })
+}
+
+// `(start_position, length)` of every free space entry in the uncompacted disk
+// layout
+fn free_space_runs(disk_map: &[DiskMapEntry]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    positioned_entries(disk_map).filter_map(|(position, entry)| match *entry {
+        DiskMapEntry::FreeSpace(length) => Some((position, length)),
+        DiskMapEntry::File { .. } => None,
+    })
+}
+
+// block-by-block disk layout, `None` marking free space
+fn disk_layout(disk_map: &[DiskMapEntry]) -> Vec<Option<usize>> {
+    disk_map
+        .iter()
+        .flat_map(|entry| match *entry {
+            DiskMapEntry::File { id, length } => repeat_n(Some(id), length),
+            DiskMapEntry::FreeSpace(length) => repeat_n(None, length),
+        })
+        .collect()
+}
+
+// renders a disk layout with each block's file id (or `.` for free space)
+// separated by `sep`, so that multi-digit ids stay readable
+#[must_use]
+pub fn render_delimited(layout: &[Option<usize>], sep: &str) -> String {
+    layout
+        .iter()
+        .map(|block| block.map_or_else(|| ".".to_string(), |id| id.to_string()))
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+const MAX_COMPACTION_FRAMES: usize = 100;
+
+// disk layout after each single block move of the part 1 compaction, starting
+// with the uncompacted layout; at most `MAX_COMPACTION_FRAMES` frames are
+// returned, the last one always being the fully compacted layout
+#[must_use]
+pub fn compaction_steps(disk_map: &[DiskMapEntry]) -> Vec<Vec<Option<usize>>> {
+    let mut layout = disk_layout(disk_map);
+    let mut frames = vec![layout.clone()];
+
+    let mut free_idx = 0;
+    let mut file_end_idx = layout.len();
+
+    loop {
+        while free_idx < layout.len() && layout[free_idx].is_some() {
+            free_idx += 1;
+        }
+
+        while file_end_idx > 0 && layout[file_end_idx - 1].is_none() {
+            file_end_idx -= 1;
+        }
+
+        if free_idx >= file_end_idx {
+            // no free space left before the last file block
+            break;
+        }
+
+        layout.swap(free_idx, file_end_idx - 1);
+
+        if frames.len() < MAX_COMPACTION_FRAMES - 1 {
+            frames.push(layout.clone());
+        }
+    }
+
+    if frames.last() != Some(&layout) {
+        frames.push(layout);
+    }
+
+    frames
+}
+
+// part 1 compaction keeping the first `reserve_blocks` blocks free - file
+// blocks found there are evicted to the first free blocks past the
+// reservation (growing the disk if there are none) before the regular
+// compaction, which never moves blocks into the reservation; returned as a
+// block-by-block layout, since the reserved prefix is free space
+#[must_use]
+pub fn compact_with_reserve(
+    disk_map: &[DiskMapEntry],
+    reserve_blocks: usize,
+) -> Vec<Option<usize>> {
+    let mut layout = disk_layout(disk_map);
+
+    if layout.len() < reserve_blocks {
+        layout.resize(reserve_blocks, None);
+    }
+
+    let mut free_idx = reserve_blocks;
+
+    for idx in 0..reserve_blocks {
+        let Some(id) = layout[idx].take() else {
+            continue;
+        };
+
+        while free_idx < layout.len() && layout[free_idx].is_some() {
+            free_idx += 1;
+        }
+
+        if free_idx == layout.len() {
+            layout.push(None);
+        }
+
+        layout[free_idx] = Some(id);
+    }
+
+    let mut file_end_idx = layout.len();
+
+    loop {
+        while free_idx < layout.len() && layout[free_idx].is_some() {
+            free_idx += 1;
+        }
+
+        while file_end_idx > 0 && layout[file_end_idx - 1].is_none() {
+            file_end_idx -= 1;
+        }
+
+        if free_idx >= file_end_idx {
+            // no free space left before the last file block
+            break;
+        }
+
+        layout.swap(free_idx, file_end_idx - 1);
+    }
+
+    layout
+}
+
+// part 1 compaction that only fills the free runs of the uncompacted layout
+// at least `min_free_run` blocks long, leaving the shorter gaps in place;
+// returned as a block-by-block layout, since gaps may remain between files
+#[must_use]
+pub fn compact_part1_threshold(
+    disk_map: &[DiskMapEntry],
+    min_free_run: usize,
+) -> Vec<Option<usize>> {
+    let mut layout = disk_layout(disk_map);
+
+    let fillable_positions = free_space_runs(disk_map)
+        .filter(|(_, length)| *length >= min_free_run)
+        .flat_map(|(position, length)| position..position + length);
+
+    let mut file_end_idx = layout.len();
+
+    for free_idx in fillable_positions {
+        while file_end_idx > 0 && layout[file_end_idx - 1].is_none() {
+            file_end_idx -= 1;
+        }
+
+        if free_idx >= file_end_idx {
+            // no file blocks left past this free block
+            break;
+        }
+
+        layout.swap(free_idx, file_end_idx - 1);
+    }
+
+    layout
+}
+
 fn compact_disk(disk_map: &[DiskMapEntry]) -> Vec<usize> {
     let mut disk_image = Vec::new();
 
@@ -60,7 +267,7 @@ fn compact_disk(disk_map: &[DiskMapEntry]) -> Vec<usize> {
                     break;
                 }
 
-                disk_image.extend(repeat(id).take(length));
+                disk_image.extend(repeat_n(id, length));
                 last_processed_file_id = Some(id);
             }
             DiskMapEntry::FreeSpace(length) => {
@@ -81,6 +288,57 @@ fn compact_disk(disk_map: &[DiskMapEntry]) -> Vec<usize> {
     disk_image
 }
 
+// moves each whole file once, highest id first, into the leftmost free span
+// (to the left of it) that is large enough to hold it
+#[must_use]
+pub fn compact_disk_whole_files(disk_map: &[DiskMapEntry]) -> Vec<Option<usize>> {
+    let mut layout = disk_layout(disk_map);
+
+    let files: Vec<_> = positioned_entries(disk_map)
+        .filter_map(|(position, entry)| match *entry {
+            DiskMapEntry::File { id, length } => Some((id, position, length)),
+            DiskMapEntry::FreeSpace(_) => None,
+        })
+        .collect();
+    let mut free_runs: Vec<_> = free_space_runs(disk_map).collect();
+
+    for &(id, file_start, length) in files.iter().rev() {
+        let Some(run) = free_runs
+            .iter_mut()
+            .take_while(|(start, _)| *start < file_start)
+            .find(|(_, free)| *free >= length)
+        else {
+            continue;
+        };
+
+        layout[run.0..run.0 + length].fill(Some(id));
+        layout[file_start..file_start + length].fill(None);
+
+        run.0 += length;
+        run.1 -= length;
+    }
+
+    layout
+}
+
+fn checksum(layout: &[Option<usize>]) -> usize {
+    layout
+        .iter()
+        .enumerate()
+        .filter_map(|(i, block)| block.map(|id| i * id))
+        .sum()
+}
+
+// part 2 checksum minus part 1 checksum - usually positive, as whole-file
+// compaction leaves gaps behind
+#[must_use]
+pub fn checksum_savings(p: &Problem) -> isize {
+    #[allow(clippy::cast_possible_wrap)]
+    let savings = solve_part_2(p) as isize - solve_part_1(p) as isize;
+
+    savings
+}
+
 #[must_use]
 pub fn solve_part_1(p: &Problem) -> usize {
     let Problem { disk_map } = p;
@@ -94,6 +352,13 @@ pub fn solve_part_1(p: &Problem) -> usize {
         .sum()
 }
 
+#[must_use]
+pub fn solve_part_2(p: &Problem) -> usize {
+    let Problem { disk_map } = p;
+
+    checksum(&compact_disk_whole_files(disk_map))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,6 +386,66 @@ mod tests {
         let p: Problem = TEST_INPUT_2.parse().unwrap();
 
         assert_eq!(p.disk_map.len(), 19);
+
+        // input files end with a newline
+        let with_newline: Problem = format!("{TEST_INPUT_2}\n").parse().unwrap();
+
+        assert_eq!(with_newline, p);
+    }
+
+    #[test]
+    fn test_validate() {
+        let p: Problem = "".parse().unwrap();
+
+        assert_eq!(p.validate().unwrap_err().to_string(), "Empty disk map");
+
+        let p: Problem = "\n".parse().unwrap();
+
+        assert!(p.validate().is_err());
+
+        let p: Problem = "05".parse().unwrap();
+
+        assert_eq!(
+            p.validate().unwrap_err().to_string(),
+            "Disk map has no non-empty files"
+        );
+
+        assert!(TEST_INPUT_1.parse::<Problem>().unwrap().validate().is_ok());
+        assert!(TEST_INPUT_2.parse::<Problem>().unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn test_free_runs() {
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        let free_runs = p.free_runs();
+
+        assert_eq!(
+            free_runs,
+            vec![
+                (2, 3),
+                (8, 3),
+                (12, 3),
+                (18, 1),
+                (21, 1),
+                (26, 1),
+                (31, 1),
+                (35, 1),
+                (40, 0)
+            ]
+        );
+
+        let odd_indexed_lengths = TEST_INPUT_2
+            .chars()
+            .skip(1)
+            .step_by(2)
+            .map(|c| c.to_digit(10).unwrap() as usize)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            free_runs.iter().map(|run| run.1).collect::<Vec<_>>(),
+            odd_indexed_lengths
+        );
     }
 
     #[test]
@@ -139,10 +464,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compact_with_reserve() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        let layout = compact_with_reserve(p.disk_map(), 2);
+
+        assert!(layout[..2].iter().all(Option::is_none));
+        assert_eq!(render_delimited(&layout, ""), "..011122222....");
+
+        assert_eq!(
+            compact_with_reserve(p.disk_map(), 0),
+            compaction_steps(p.disk_map()).pop().unwrap()
+        );
+
+        // no free space at all, the evicted block lands past the disk end
+        let p: Problem = "3".parse().unwrap();
+
+        assert_eq!(
+            compact_with_reserve(p.disk_map(), 1),
+            vec![None, Some(0), Some(0), Some(0)]
+        );
+    }
+
+    #[test]
+    fn test_compact_part1_threshold() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        assert_eq!(
+            render_delimited(&compact_part1_threshold(p.disk_map(), 2), ""),
+            "022111222......"
+        );
+        assert_eq!(
+            render_delimited(&compact_part1_threshold(p.disk_map(), 3), ""),
+            "0..11122222...."
+        );
+        assert_eq!(
+            compact_part1_threshold(p.disk_map(), 5),
+            disk_layout(p.disk_map())
+        );
+
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        assert_eq!(
+            compact_part1_threshold(p.disk_map(), 0)
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>(),
+            compact_disk(p.disk_map())
+        );
+    }
+
+    #[test]
+    fn test_render_delimited() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        assert_eq!(
+            render_delimited(&disk_layout(p.disk_map()), " "),
+            "0 . . 1 1 1 . . . . 2 2 2 2 2"
+        );
+        assert_eq!(
+            render_delimited(&[Some(0), Some(12), None, Some(3), Some(12)], "|"),
+            "0|12|.|3|12"
+        );
+        assert_eq!(render_delimited(&[], " "), "");
+    }
+
+    #[test]
+    fn test_compaction_steps() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        let frames = compaction_steps(p.disk_map());
+
+        // 0..111....22222
+        // 02.111....2222.
+        // 022111....222..
+        // 0221112...22...
+        // 02211122..2....
+        // 022111222......
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0], disk_layout(p.disk_map()));
+        assert_eq!(
+            frames[1],
+            vec![
+                Some(0),
+                Some(2),
+                None,
+                Some(1),
+                Some(1),
+                Some(1),
+                None,
+                None,
+                None,
+                None,
+                Some(2),
+                Some(2),
+                Some(2),
+                Some(2),
+                None
+            ]
+        );
+        assert_eq!(
+            frames[5],
+            compact_disk(p.disk_map())
+                .into_iter()
+                .map(Some)
+                .chain(repeat_n(None, 6))
+                .collect::<Vec<_>>()
+        );
+
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        let frames = compaction_steps(p.disk_map());
+
+        assert_eq!(
+            frames
+                .last()
+                .unwrap()
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>(),
+            compact_disk(p.disk_map())
+        );
+
+        let p: Problem = "9".repeat(999).parse().unwrap();
+
+        let frames = compaction_steps(p.disk_map());
+
+        assert_eq!(frames.len(), MAX_COMPACTION_FRAMES);
+        assert!(frames[MAX_COMPACTION_FRAMES - 1]
+            .iter()
+            .skip_while(|block| block.is_some())
+            .all(Option::is_none));
+    }
+
     #[test]
     fn test_solve_part_1() {
         let p: Problem = TEST_INPUT_2.parse().unwrap();
 
         assert_eq!(solve_part_1(&p), 1928);
     }
+
+    #[test]
+    fn test_compact_disk_whole_files() {
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        assert_eq!(
+            render_delimited(&compact_disk_whole_files(p.disk_map()), ""),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+    }
+
+    #[test]
+    fn test_solve_part_2() {
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        assert_eq!(solve_part_2(&p), 2858);
+    }
+
+    #[test]
+    fn test_checksum_savings() {
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        assert_eq!(checksum_savings(&p), 2858 - 1928);
+        assert_eq!(checksum_savings(&p), 930);
+    }
 }
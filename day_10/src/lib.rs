@@ -32,6 +32,36 @@ impl FromStr for Problem {
     }
 }
 
+impl Problem {
+    /// Parses an ASCII map directly from bytes, skipping UTF-8 validation.
+    /// Lines are split the same way as [`str::lines`] does, so this yields the
+    /// same map as parsing the input as a string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the map contains anything other than digits and `.`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Problem, anyhow::Error> {
+        let map = bytes
+            .split_inclusive(|&b| b == b'\n')
+            .map(|l| {
+                l.strip_suffix(b"\n")
+                    .map_or(l, |l| l.strip_suffix(b"\r").unwrap_or(l))
+            })
+            .map(|l| {
+                l.iter()
+                    .map(|&b| match b {
+                        b'.' => Ok(u8::MAX),
+                        b'0'..=b'9' => Ok(b - b'0'),
+                        _ => Err(anyhow!("Couldn't parse digit from byte")),
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Problem { map })
+    }
+}
+
 fn neighbour_indices(
     x: usize,
     y: usize,
@@ -51,7 +81,47 @@ fn neighbour_indices(
     })
 }
 
+// like `neighbour_indices`, but including the four diagonal neighbours too
+fn neighbour_indices_8(
+    x: usize,
+    y: usize,
+    max_x: usize,
+    max_y: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    let diagonals = [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+        .into_iter()
+        .filter_map(
+            move |(dx, dy)| match (x.checked_add_signed(dx), y.checked_add_signed(dy)) {
+                (Some(a), Some(b)) if a <= max_x && b <= max_y => Some((a, b)),
+                _ => None,
+            },
+        );
+
+    neighbour_indices(x, y, max_x, max_y).chain(diagonals)
+}
+
 fn score_trailhead(map: &[Vec<u8>], starting_point: (usize, usize)) -> usize {
+    score_trailhead_multi(map, starting_point, &[1])
+}
+
+// like `score_trailhead`, but a trail may go up by any of `allowed_steps` at
+// a time
+#[must_use]
+pub fn score_trailhead_multi(
+    map: &[Vec<u8>],
+    starting_point: (usize, usize),
+    allowed_steps: &[u8],
+) -> usize {
+    score_trailhead_with(map, starting_point, allowed_steps, false)
+}
+
+// when `diagonal` is set, trails may also step to diagonally adjacent cells
+fn score_trailhead_with(
+    map: &[Vec<u8>],
+    starting_point: (usize, usize),
+    allowed_steps: &[u8],
+    diagonal: bool,
+) -> usize {
     let max_x = map.len() - 1;
     let max_y = map[0].len() - 1;
     let mut q = VecDeque::from([starting_point]);
@@ -73,12 +143,83 @@ fn score_trailhead(map: &[Vec<u8>], starting_point: (usize, usize)) -> usize {
             continue;
         }
 
+        let neighbours: Vec<_> = if diagonal {
+            neighbour_indices_8(x, y, max_x, max_y).collect()
+        } else {
+            neighbour_indices(x, y, max_x, max_y).collect()
+        };
+
+        q.extend(neighbours.into_iter().filter(|n| {
+            allowed_steps
+                .iter()
+                .any(|step| height.checked_add(*step) == Some(map[n.0][n.1]))
+                && !visited.contains(n)
+        }));
+    }
+
+    peaks.len()
+}
+
+// every step a trail can take - from a cell to an orthogonal neighbour one
+// higher - in reading order of the cells stepped from
+#[must_use]
+pub fn trail_edges(map: &[Vec<u8>]) -> Vec<((usize, usize), (usize, usize))> {
+    let mut edges = vec![];
+
+    for (x, row) in map.iter().enumerate() {
+        for (y, height) in row.iter().enumerate() {
+            edges.extend(
+                neighbour_indices(x, y, map.len() - 1, row.len() - 1)
+                    .filter(|n| height.checked_add(1) == Some(map[n.0][n.1]))
+                    .map(|n| ((x, y), n)),
+            );
+        }
+    }
+
+    edges
+}
+
+// whether a trail leads from `from` up to `to`, which is never the case when
+// either of them lies outside the map
+#[must_use]
+pub fn can_reach(map: &[Vec<u8>], from: (usize, usize), to: (usize, usize)) -> bool {
+    let height_at = |(x, y): (usize, usize)| map.get(x).and_then(|row| row.get(y));
+
+    let (Some(_), Some(&target_height)) = (height_at(from), height_at(to)) else {
+        return false;
+    };
+
+    let max_x = map.len() - 1;
+    let max_y = map[0].len() - 1;
+    let mut q = VecDeque::from([from]);
+    let mut visited: HashSet<(usize, usize)> = HashSet::new();
+
+    while let Some((x, y)) = q.pop_front() {
+        if (x, y) == to {
+            return true;
+        }
+
+        if visited.contains(&(x, y)) {
+            continue;
+        }
+
+        visited.insert((x, y));
+
+        let height = map[x][y];
+
+        // trails only go up, no point in climbing past the target
+        if height >= target_height {
+            continue;
+        }
+
         let neighbours = neighbour_indices(x, y, max_x, max_y);
 
-        q.extend(neighbours.filter(|n| map[n.0][n.1] == height + 1 && !visited.contains(n)));
+        q.extend(
+            neighbours.filter(|&n| height_at(n) == Some(&(height + 1)) && !visited.contains(&n)),
+        );
     }
 
-    peaks.len()
+    false
 }
 
 fn rate_trailhead(map: &[Vec<u8>], starting_point: (usize, usize)) -> usize {
@@ -114,6 +255,87 @@ pub fn solve_part_1(p: &Problem) -> usize {
     score
 }
 
+// like `solve_part_1`, but only trailheads within the rectangle spanned by the
+// (inclusive) `top_left` and `bottom_right` corners are scored - trails may
+// still leave the rectangle
+#[must_use]
+pub fn solve_part_1_region(
+    p: &Problem,
+    top_left: (usize, usize),
+    bottom_right: (usize, usize),
+) -> usize {
+    let Problem { map } = p;
+
+    let mut score = 0;
+
+    for x in top_left.0..bottom_right.0.saturating_add(1).min(map.len()) {
+        for y in top_left.1..bottom_right.1.saturating_add(1).min(map[x].len()) {
+            if map[x][y] == 0 {
+                score += score_trailhead(map, (x, y));
+            }
+        }
+    }
+
+    score
+}
+
+// `diagonal` selects 8-connectivity for trail steps instead of the regular
+// 4-connectivity
+#[must_use]
+pub fn solve_part_1_connected(p: &Problem, diagonal: bool) -> usize {
+    let Problem { map } = p;
+
+    let mut score = 0;
+
+    for x in 0..map.len() {
+        for y in 0..map[0].len() {
+            if map[x][y] == 0 {
+                score += score_trailhead_with(map, (x, y), &[1], diagonal);
+            }
+        }
+    }
+
+    score
+}
+
+// height 9 cells no hiking trail leads to, sorted by `(x, y)`
+#[must_use]
+pub fn unreachable_peaks(p: &Problem) -> Vec<(usize, usize)> {
+    let Problem { map } = p;
+
+    // an empty map has no peaks to begin with
+    let (Some(max_x), Some(max_y)) = (
+        map.len().checked_sub(1),
+        map.first().map_or(0, Vec::len).checked_sub(1),
+    ) else {
+        return Vec::new();
+    };
+
+    // walk up from all trailheads at once
+    let mut q: VecDeque<(usize, usize)> = (0..=max_x)
+        .flat_map(|x| (0..=max_y).map(move |y| (x, y)))
+        .filter(|&(x, y)| map[x][y] == 0)
+        .collect();
+    let mut visited: HashSet<(usize, usize)> = HashSet::new();
+
+    while let Some((x, y)) = q.pop_front() {
+        if !visited.insert((x, y)) {
+            continue;
+        }
+
+        let height = map[x][y];
+
+        let neighbours = neighbour_indices(x, y, max_x, max_y);
+
+        q.extend(neighbours.filter(|n| map[n.0][n.1] == height + 1 && !visited.contains(n)));
+    }
+
+    (0..=max_x)
+        .flat_map(|x| (0..=max_y).map(move |y| (x, y)))
+        .filter(|&(x, y)| map[x][y] == 9 && !visited.contains(&(x, y)))
+        .collect()
+}
+
 #[must_use]
 pub fn solve_part_2(p: &Problem) -> usize {
     let Problem { map } = p;
@@ -131,6 +353,24 @@ pub fn solve_part_2(p: &Problem) -> usize {
     score
 }
 
+// like `solve_part_2`, but each trailhead contributes at most `cap`
+#[must_use]
+pub fn solve_part_2_capped(p: &Problem, cap: usize) -> usize {
+    let Problem { map } = p;
+
+    let mut score = 0;
+
+    for x in 0..map.len() {
+        for y in 0..map[0].len() {
+            if map[x][y] == 0 {
+                score += rate_trailhead(map, (x, y)).min(cap);
+            }
+        }
+    }
+
+    score
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +411,30 @@ mod tests {
         assert_eq!(p.map[2][0], 8);
     }
 
+    #[test]
+    fn test_problem_from_bytes() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        assert_eq!(Problem::from_bytes(TEST_INPUT_1.as_bytes()).unwrap(), p);
+        assert_eq!(Problem::from_bytes(b"0123\n1234\n8765\n9876\n").unwrap(), p);
+        assert_eq!(
+            Problem::from_bytes(b"..90..9\n...1.98").unwrap(),
+            "..90..9\n...1.98".parse().unwrap()
+        );
+        assert!(Problem::from_bytes(b"01x3").is_err());
+
+        for input in ["0123\n\n1234\n", "0123\r\n\r\n1234", "0123\n\n", ""] {
+            assert_eq!(
+                Problem::from_bytes(input.as_bytes()).unwrap(),
+                input.parse().unwrap()
+            );
+        }
+
+        // a `\r` not followed by `\n` isn't a line ending
+        assert!(Problem::from_bytes(b"123\r").is_err());
+        assert!("123\r".parse::<Problem>().is_err());
+    }
+
     #[test]
     fn test_score_tailhead() {
         let p: Problem = TEST_INPUT_1.parse().unwrap();
@@ -182,6 +446,49 @@ mod tests {
         assert_eq!(score_trailhead(&p.map, (0, 3)), 2);
     }
 
+    #[test]
+    fn test_score_trailhead_multi() {
+        let p: Problem = "013456789".parse().unwrap();
+
+        assert_eq!(score_trailhead(&p.map, (0, 0)), 0);
+        assert_eq!(score_trailhead_multi(&p.map, (0, 0), &[1]), 0);
+        assert_eq!(score_trailhead_multi(&p.map, (0, 0), &[1, 2]), 1);
+
+        let p: Problem = TEST_INPUT_3.parse().unwrap();
+
+        assert_eq!(score_trailhead_multi(&p.map, (0, 3), &[1]), 2);
+    }
+
+    #[test]
+    fn test_trail_edges() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        let edges = trail_edges(&p.map);
+
+        // 3 in each row, plus 2 + 2 + 2 + 3 in the columns
+        assert_eq!(edges.len(), 4 * 3 + 2 + 2 + 2 + 3);
+        assert_eq!(edges[..2], [((0, 0), (1, 0)), ((0, 0), (0, 1))]);
+        assert!(!edges.contains(&((3, 0), (2, 0))));
+        assert!(edges.contains(&((2, 0), (3, 0))));
+        assert!(edges
+            .iter()
+            .all(|&((x, y), (nx, ny))| p.map[x][y] + 1 == p.map[nx][ny]));
+    }
+
+    #[test]
+    fn test_can_reach() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        assert!(can_reach(&p.map, (0, 0), (3, 0)));
+        assert!(can_reach(&p.map, (0, 0), (0, 0)));
+        assert!(can_reach(&p.map, (1, 3), (2, 0)));
+        assert!(!can_reach(&p.map, (3, 0), (0, 0)));
+        assert!(!can_reach(&p.map, (0, 1), (1, 0)));
+        assert!(!can_reach(&p.map, (0, 0), (4, 0)));
+        assert!(!can_reach(&p.map, (0, 4), (0, 0)));
+        assert!(!can_reach(&[], (0, 0), (0, 0)));
+    }
+
     #[test]
     fn test_solve_part_1() {
         let p: Problem = TEST_INPUT_2.parse().unwrap();
@@ -189,6 +496,68 @@ mod tests {
         assert_eq!(solve_part_1(&p), 36);
     }
 
+    #[test]
+    fn test_solve_part_1_region() {
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        // the only trailhead in the top-left quadrant is (0, 2)
+        assert_eq!(solve_part_1_region(&p, (0, 0), (3, 3)), 5);
+        // (4, 6), (5, 5) and (6, 6)
+        assert_eq!(solve_part_1_region(&p, (4, 4), (7, 7)), 3 + 1 + 5);
+        assert_eq!(solve_part_1_region(&p, (0, 0), (7, 7)), 36);
+        assert_eq!(solve_part_1_region(&p, (0, 0), (100, 100)), 36);
+
+        let p: Problem = "".parse().unwrap();
+
+        assert_eq!(solve_part_1_region(&p, (0, 0), (3, 3)), 0);
+    }
+
+    #[test]
+    fn test_solve_part_1_connected() {
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        assert_eq!(solve_part_1_connected(&p, false), 36);
+        assert!(solve_part_1_connected(&p, true) > 36);
+
+        let p: Problem = "\
+01
+.2"
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            neighbour_indices_8(0, 0, 1, 1).collect::<HashSet<_>>(),
+            HashSet::from([(1, 0), (0, 1), (1, 1)])
+        );
+        assert_eq!(neighbour_indices_8(1, 1, 2, 2).count(), 8);
+        assert_eq!(solve_part_1_connected(&p, false), 0);
+    }
+
+    #[test]
+    fn test_unreachable_peaks() {
+        let p: Problem = TEST_INPUT_1.parse().unwrap();
+
+        assert!(unreachable_peaks(&p).is_empty());
+
+        let p: Problem = "\
+0123
+1234
+8765
+9879"
+            .parse()
+            .unwrap();
+
+        assert_eq!(unreachable_peaks(&p), vec![(3, 3)]);
+
+        let p: Problem = TEST_INPUT_3.parse().unwrap();
+
+        assert!(unreachable_peaks(&p).is_empty());
+
+        let p: Problem = "".parse().unwrap();
+
+        assert!(unreachable_peaks(&p).is_empty());
+    }
+
     #[test]
     fn test_rate_trailhead() {
         let p: Problem = "\
@@ -236,4 +605,18 @@ mod tests {
 
         assert_eq!(solve_part_2(&p), 81);
     }
+
+    #[test]
+    fn test_solve_part_2_capped() {
+        let p: Problem = TEST_INPUT_2.parse().unwrap();
+
+        // trailhead ratings are 20, 24, 10, 4, 1, 4, 5, 8 and 5
+        assert_eq!(
+            solve_part_2_capped(&p, 5),
+            5 + 5 + 5 + 4 + 1 + 4 + 5 + 5 + 5
+        );
+        assert!(solve_part_2_capped(&p, 5) <= 81);
+        assert_eq!(solve_part_2_capped(&p, 0), 0);
+        assert_eq!(solve_part_2_capped(&p, usize::MAX), 81);
+    }
 }
@@ -1,7 +1,7 @@
 use std::{env, fs};
 
 use anyhow::Context;
-use day_11::{solve_part_1, Problem};
+use day_11::{solve_part_1, solve_part_2, Problem};
 
 fn main() -> Result<(), anyhow::Error> {
     let input_path = env::args().nth(1).context("missing path argument")?;
@@ -9,6 +9,7 @@ fn main() -> Result<(), anyhow::Error> {
     let p: Problem = input.parse()?;
 
     println!("Part 1: {}", solve_part_1(&p));
+    println!("Part 2: {}", solve_part_2(&p));
 
     Ok(())
 }
@@ -1,8 +1,13 @@
-use std::{iter::successors, str::FromStr};
+use anyhow::Context;
+use std::{
+    collections::{HashMap, HashSet},
+    iter::{successors, Sum},
+    str::FromStr,
+};
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct Problem {
-    stones: Vec<usize>,
+    stones: Vec<u64>,
 }
 
 impl FromStr for Problem {
@@ -12,27 +17,59 @@ impl FromStr for Problem {
         let stones = s
             .trim()
             .split_ascii_whitespace()
-            .map(str::parse)
-            .collect::<Result<Vec<usize>, _>>()?;
+            .enumerate()
+            .map(|(idx, token)| {
+                token
+                    .parse()
+                    .with_context(|| format!("invalid stone '{token}' at index {idx}"))
+            })
+            .collect::<Result<Vec<u64>, _>>()?;
 
         Ok(Problem { stones })
     }
 }
 
-fn split_if_even_number_of_digits(n: usize) -> Option<(usize, usize)> {
+fn split_if_even_number_of_digits(n: u64) -> Option<(u64, u64)> {
     let nb_of_digits = n.ilog10() + 1;
 
-    if nb_of_digits % 2 != 0 {
+    if !nb_of_digits.is_multiple_of(2) {
         return None;
     }
 
     Some((
-        n / 10usize.pow(nb_of_digits / 2),
-        n % 10usize.pow(nb_of_digits / 2),
+        n / 10u64.pow(nb_of_digits / 2),
+        n % 10u64.pow(nb_of_digits / 2),
     ))
 }
 
-fn watch_stones(stones: &[usize]) -> Vec<usize> {
+// number of blinks `n` survives as a single stone before it splits in two, or
+// `None` if it splits right on the first blink or would no longer fit in `u64`
+// before splitting
+#[must_use]
+pub fn first_split_round(n: u64) -> Option<usize> {
+    let mut n = n;
+    let mut unsplit_rounds = 0;
+
+    while n == 0 || split_if_even_number_of_digits(n).is_none() {
+        n = if n == 0 { 1 } else { n.checked_mul(2024)? };
+        unsplit_rounds += 1;
+    }
+
+    (unsplit_rounds > 0).then_some(unsplit_rounds)
+}
+
+#[must_use]
+pub fn is_terminal_free(n: u64, blinks: usize) -> bool {
+    first_split_round(n).unwrap_or(0) >= blinks
+}
+
+fn watch_stones(stones: &[u64]) -> Vec<u64> {
+    watch_stones_with(stones, 2024)
+}
+
+// same as `watch_stones`, but stones that neither are 0 nor split get
+// multiplied by `multiplier` instead of 2024
+fn watch_stones_with(stones: &[u64], multiplier: u64) -> Vec<u64> {
     stones
         .iter()
         .flat_map(|&n| {
@@ -44,17 +81,115 @@ fn watch_stones(stones: &[usize]) -> Vec<usize> {
                 return vec![a, b];
             }
 
-            vec![n * 2024]
+            vec![n * multiplier]
         })
         .collect()
 }
 
-fn blink(stones: &[usize], times: usize) -> Vec<usize> {
+fn blink(stones: &[u64], times: usize) -> Vec<u64> {
     successors(Some(stones.to_vec()), |stones| Some(watch_stones(stones)))
         .nth(times)
         .expect("Shouldn't happen")
 }
 
+// number of stones `n` turns into after `blinks` blinks, memoized on
+// `(n, blinks)` since stone order doesn't matter for the count
+fn count_stones(n: u64, blinks: usize, cache: &mut HashMap<(u64, usize), usize>) -> usize {
+    count_stones_with(n, blinks, 2024, cache)
+}
+
+// `cache` must only ever be shared between calls with the same `multiplier`;
+// generic over the count type, so that huge counts can use a wider one
+fn count_stones_with<T: Copy + From<u8> + Sum>(
+    n: u64,
+    blinks: usize,
+    multiplier: u64,
+    cache: &mut HashMap<(u64, usize), T>,
+) -> T {
+    if blinks == 0 {
+        return T::from(1);
+    }
+
+    if let Some(count) = cache.get(&(n, blinks)) {
+        return *count;
+    }
+
+    let count = watch_stones_with(&[n], multiplier)
+        .into_iter()
+        .map(|n| count_stones_with(n, blinks - 1, multiplier, cache))
+        .sum();
+
+    cache.insert((n, blinks), count);
+
+    count
+}
+
+// number of stones after 0, 1, ..., `up_to` blinks, tracking how many stones
+// carry each value instead of the stones themselves
+#[must_use]
+pub fn counts_for_blinks(p: &Problem, up_to: usize) -> Vec<usize> {
+    let Problem { stones } = p;
+
+    let mut value_counts: HashMap<u64, usize> = HashMap::new();
+
+    for &n in stones {
+        *value_counts.entry(n).or_default() += 1;
+    }
+
+    let mut counts = vec![stones.len()];
+
+    for _ in 0..up_to {
+        let mut next_value_counts: HashMap<u64, usize> = HashMap::new();
+
+        for (n, count) in value_counts {
+            for next_n in watch_stones(&[n]) {
+                *next_value_counts.entry(next_n).or_default() += count;
+            }
+        }
+
+        value_counts = next_value_counts;
+        counts.push(value_counts.values().sum());
+    }
+
+    counts
+}
+
+// number of different values carried by the stones after `blinks` blinks -
+// unlike the number of stones, this stops growing at some point, as only
+// finitely many values are ever reachable
+#[must_use]
+pub fn distinct_values_after(p: &Problem, blinks: usize) -> usize {
+    let Problem { stones } = p;
+
+    let mut values: HashSet<u64> = stones.iter().copied().collect();
+
+    for _ in 0..blinks {
+        values = values
+            .into_iter()
+            .flat_map(|n| watch_stones(&[n]))
+            .collect();
+    }
+
+    values.len()
+}
+
+// keeps the `count_stones` cache around, so it can be shared between problems
+#[derive(Debug, Default)]
+pub struct Solver {
+    cache: HashMap<(u64, usize), usize>,
+}
+
+impl Solver {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn count(&mut self, value: u64, steps: usize) -> usize {
+        count_stones(value, steps, &mut self.cache)
+    }
+}
+
 #[must_use]
 pub fn solve_part_1(p: &Problem) -> usize {
     let Problem { stones } = p;
@@ -64,6 +199,55 @@ pub fn solve_part_1(p: &Problem) -> usize {
     stones.len()
 }
 
+#[must_use]
+pub fn solve_part_2(p: &Problem) -> usize {
+    solve_with_multiplier(p, 75, 2024)
+}
+
+// number of stones after `blinks` blinks for a puzzle variant multiplying
+// stones by `multiplier` instead of 2024
+#[must_use]
+pub fn solve_with_multiplier(p: &Problem, blinks: usize, multiplier: u64) -> usize {
+    let Problem { stones } = p;
+
+    let mut cache: HashMap<(u64, usize), usize> = HashMap::new();
+
+    stones
+        .iter()
+        .map(|&n| count_stones_with(n, blinks, multiplier, &mut cache))
+        .sum()
+}
+
+// same as `solve_with_multiplier` for the regular 2024 multiplier, but counts
+// stones as `u128` so that even extreme numbers of blinks don't overflow
+#[must_use]
+pub fn solve_u128(p: &Problem, blinks: usize) -> u128 {
+    let Problem { stones } = p;
+
+    let mut cache: HashMap<(u64, usize), u128> = HashMap::new();
+
+    stones
+        .iter()
+        .map(|&n| count_stones_with(n, blinks, 2024, &mut cache))
+        .sum()
+}
+
+// same as `solve_part_2`, but counts initial stones in parallel; `map_init`
+// starts a fresh cache for every chunk of stones rayon splits off, so the
+// chunks don't share what they've already counted
+#[cfg(feature = "rayon")]
+#[must_use]
+pub fn solve_part_2_parallel(p: &Problem) -> usize {
+    use rayon::prelude::*;
+
+    let Problem { stones } = p;
+
+    stones
+        .par_iter()
+        .map_init(HashMap::new, |cache, &n| count_stones(n, 75, cache))
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,12 +262,19 @@ mod tests {
             Problem {
                 stones: vec![0, 1, 10, 99, 999],
             }
-        )
+        );
+    }
+
+    #[test]
+    fn test_problem_parsing_invalid_stone() {
+        let err = "125 x 17".parse::<Problem>().unwrap_err();
+
+        assert_eq!(err.to_string(), "invalid stone 'x' at index 1");
     }
 
     #[test]
     fn test_if_i_remember_basic_math_correctly() {
-        let n = 123456usize;
+        let n = 123_456_usize;
         let nb_of_digits = n.ilog10() + 1;
 
         assert_eq!(n / 10usize.pow(nb_of_digits / 2), 123);
@@ -95,32 +286,75 @@ mod tests {
         assert_eq!(n / 10usize.pow(nb_of_digits / 2), 99);
         assert_eq!(n % 10usize.pow(nb_of_digits / 2), 99);
 
-        let n = 987001usize;
+        let n = 987_001_usize;
         let nb_of_digits = n.ilog10() + 1;
 
         assert_eq!(n / 10usize.pow(nb_of_digits / 2), 987);
         assert_eq!(n % 10usize.pow(nb_of_digits / 2), 1);
     }
 
+    #[test]
+    fn test_first_split_round() {
+        // 0 -> 1 -> 2024 -> 20 24
+        assert_eq!(first_split_round(0), Some(2));
+        // 1 -> 2024 -> 20 24
+        assert_eq!(first_split_round(1), Some(1));
+        // 5 -> 10120 -> 20482880 -> 2048 2880
+        assert_eq!(first_split_round(5), Some(2));
+        assert_eq!(first_split_round(125), Some(1));
+        assert_eq!(first_split_round(10), None);
+        assert_eq!(first_split_round(2024), None);
+        // 17 digits, and 10^16 * 2024 no longer fits in `u64`
+        assert_eq!(first_split_round(10_000_000_000_000_000), None);
+    }
+
+    #[test]
+    fn test_is_terminal_free() {
+        assert!(is_terminal_free(0, 2));
+        assert!(!is_terminal_free(0, 3));
+        assert!(is_terminal_free(10, 0));
+        assert!(!is_terminal_free(10, 1));
+    }
+
     #[test]
     fn test_watch_stones() {
         let stones = vec![0, 1, 10, 99, 999];
 
-        assert_eq!(watch_stones(&stones), vec![1, 2024, 1, 0, 9, 9, 2021976]);
+        assert_eq!(watch_stones(&stones), vec![1, 2024, 1, 0, 9, 9, 2_021_976]);
     }
 
     #[test]
     fn test_blink() {
         assert_eq!(
-            blink(&vec![0, 1, 10, 99, 999], 1),
-            vec![1, 2024, 1, 0, 9, 9, 2021976]
+            blink(&[0, 1, 10, 99, 999], 1),
+            vec![1, 2024, 1, 0, 9, 9, 2_021_976]
         );
 
         assert_eq!(
-            blink(&vec![125, 17], 6),
+            blink(&[125, 17], 6),
             vec![
-                2097446912, 14168, 4048, 2, 0, 2, 4, 40, 48, 2024, 40, 48, 80, 96, 2, 8, 6, 7, 6,
-                0, 3, 2
+                2_097_446_912,
+                14168,
+                4048,
+                2,
+                0,
+                2,
+                4,
+                40,
+                48,
+                2024,
+                40,
+                48,
+                80,
+                96,
+                2,
+                8,
+                6,
+                7,
+                6,
+                0,
+                3,
+                2
             ]
         );
     }
@@ -131,4 +365,111 @@ mod tests {
 
         assert_eq!(solve_part_1(&p), 55312);
     }
+
+    #[test]
+    fn test_count_stones() {
+        let mut cache = HashMap::new();
+
+        assert_eq!(
+            count_stones(125, 6, &mut cache) + count_stones(17, 6, &mut cache),
+            22
+        );
+        assert_eq!(
+            count_stones(125, 25, &mut cache) + count_stones(17, 25, &mut cache),
+            55312
+        );
+    }
+
+    #[test]
+    fn test_counts_for_blinks() {
+        let p: Problem = "125 17".parse().unwrap();
+
+        let counts = counts_for_blinks(&p, 25);
+
+        assert_eq!(counts.len(), 26);
+        assert_eq!(counts[..7], [2, 3, 4, 5, 9, 13, 22]);
+        assert_eq!(counts[6], blink(&p.stones, 6).len());
+        assert_eq!(counts[25], 55312);
+        assert_eq!(counts_for_blinks(&p, 0), vec![2]);
+    }
+
+    #[test]
+    fn test_solver() {
+        let mut solver = Solver::new();
+
+        let p: Problem = "125 17".parse().unwrap();
+
+        assert_eq!(
+            p.stones.iter().map(|&n| solver.count(n, 25)).sum::<usize>(),
+            55312
+        );
+
+        let cache_size = solver.cache.len();
+
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(
+            p.stones.iter().map(|&n| solver.count(n, 25)).sum::<usize>(),
+            solve_part_1(&p)
+        );
+        assert!(solver.cache.len() > cache_size);
+    }
+
+    #[test]
+    fn test_solve_part_2() {
+        let p: Problem = "125 17".parse().unwrap();
+
+        assert_eq!(solve_part_2(&p), 65_601_038_650_482);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_solve_part_2_parallel() {
+        let p: Problem = "0 1 10 99 999 125 17".parse().unwrap();
+
+        assert_eq!(solve_part_2_parallel(&p), solve_part_2(&p));
+    }
+
+    #[test]
+    fn test_solve_with_multiplier() {
+        let p: Problem = "125 17".parse().unwrap();
+
+        assert_eq!(solve_with_multiplier(&p, 25, 2024), 55312);
+        assert_eq!(solve_with_multiplier(&p, 25, 2024), solve_part_1(&p));
+
+        // 1 -> 10 -> 1 0 -> 10 1 -> 1 0 10 -> 10 1 1 0
+        let p: Problem = "1".parse().unwrap();
+
+        assert_eq!(solve_with_multiplier(&p, 5, 10), 4);
+        assert_eq!(watch_stones_with(&[1, 7], 10), vec![10, 70]);
+    }
+
+    #[test]
+    fn test_solve_u128() {
+        let p: Problem = "125 17".parse().unwrap();
+
+        assert_eq!(solve_u128(&p, 6), 22);
+        assert_eq!(solve_u128(&p, 25), 55312);
+        assert_eq!(solve_u128(&p, 75), solve_part_2(&p) as u128);
+
+        let p: Problem = TEST_INPUT.parse().unwrap();
+
+        assert_eq!(solve_u128(&p, 1), 7);
+        assert!(solve_u128(&p, 100) > u128::from(u64::MAX) / 1_000_000);
+    }
+
+    #[test]
+    fn test_distinct_values_after() {
+        let p: Problem = "125 17".parse().unwrap();
+
+        assert_eq!(distinct_values_after(&p, 0), 2);
+        assert_eq!(distinct_values_after(&p, 6), 15);
+        assert!(distinct_values_after(&p, 16) < 54);
+
+        for blinks in 17..40 {
+            assert_eq!(distinct_values_after(&p, blinks), 54);
+        }
+
+        assert!(distinct_values_after(&p, 25) < solve_part_1(&p));
+    }
 }